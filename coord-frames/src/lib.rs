@@ -1,16 +1,143 @@
-#![feature(self_struct_ctor)]
-#![feature(const_fn)]
 extern crate dimensioned as dim;
+extern crate num_traits;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 #[cfg(test)]
 #[macro_use]
 extern crate assert_approx_eq;
-use dim::si;
+use num_traits::{Float, FloatConst};
+use std::fmt::Debug;
 use std::marker::PhantomData;
 
-pub type Meter = dim::si::Meter<f64>;
+pub type Meter<N = f64> = dim::si::Meter<N>;
+
+/// The float types frame math can be done in, in practice `f32` and `f64`.
+pub trait Scalar: Float + FloatConst + Default + Debug + 'static {}
+
+impl<N: Float + FloatConst + Default + Debug + 'static> Scalar for N {}
+
+/// Strips the units off of a length, leaving its value in meters.
+#[inline]
+fn meters<N>(m: Meter<N>) -> N {
+    m.value_unsafe
+}
+
+/// Rotations used to be plain radians, and could wander outside (-π, π].
+#[deprecated(note = "use `Angle`, which keeps rotations wrapped")]
 pub type Radians = f64;
 
+/// A planar angle, always kept wrapped to (-π, π]. Angles aren't ordered, since which of two is
+/// larger depends on where the wrap is.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Angle<N = f64>(N);
+
+impl<N: Scalar> Angle<N> {
+    /// An angle of `r` radians as given, which must already be in (-π, π]. Unlike `from_radians`
+    /// it can be used in constants.
+    #[inline]
+    pub const fn from_normalized_radians(r: N) -> Self {
+        Angle(r)
+    }
+
+    #[inline]
+    pub fn from_radians(r: N) -> Self {
+        let two_pi = N::PI() + N::PI();
+        let mut r = r % two_pi;
+        if r <= -N::PI() {
+            r = r + two_pi;
+        } else if r > N::PI() {
+            r = r - two_pi;
+        }
+        Angle(r)
+    }
+
+    #[inline]
+    pub fn from_degrees(d: N) -> Self {
+        Self::from_radians(d.to_radians())
+    }
+
+    #[inline]
+    pub fn radians(&self) -> N {
+        self.0
+    }
+
+    #[inline]
+    pub fn degrees(&self) -> N {
+        self.0.to_degrees()
+    }
+
+    #[inline]
+    pub fn sin(&self) -> N {
+        self.0.sin()
+    }
+
+    #[inline]
+    pub fn cos(&self) -> N {
+        self.0.cos()
+    }
+
+    /// The smallest rotation that takes `self` to `other`, positive counterclockwise.
+    #[inline]
+    pub fn shortest_diff(&self, other: Self) -> Self {
+        other - *self
+    }
+
+    /// The magnitude of the smallest rotation between `self` and `other`, in radians.
+    #[inline]
+    pub fn distance(&self, other: Self) -> N {
+        self.shortest_diff(other).0.abs()
+    }
+}
+
+impl<N: Scalar> std::ops::Add for Angle<N> {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self::from_radians(self.0 + rhs.0)
+    }
+}
+
+impl<N: Scalar> std::ops::Sub for Angle<N> {
+    type Output = Self;
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_radians(self.0 - rhs.0)
+    }
+}
+
+impl<N: Scalar> std::ops::Neg for Angle<N> {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self {
+        Self::from_radians(-self.0)
+    }
+}
+
+impl<N: Scalar> std::ops::Mul<N> for Angle<N> {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: N) -> Self {
+        Self::from_radians(self.0 * rhs)
+    }
+}
+
+/// Serializes as radians.
+#[cfg(feature = "serde")]
+impl<N: Scalar + serde::Serialize> serde::Serialize for Angle<N> {
+    fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, N: Scalar + serde::Deserialize<'de>> serde::Deserialize<'de> for Angle<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        N::deserialize(deserializer).map(Self::from_radians)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Axis {
     X,
@@ -19,9 +146,50 @@ pub enum Axis {
 
 // /// Represents a Rigid transformation in two dimensions, a rotation
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub struct PointData {
-    pos: (Meter, Meter),
-    rot: Radians,
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(
+        from = "RawPointData<N>",
+        into = "RawPointData<N>",
+        bound(
+            serialize = "N: Scalar + serde::Serialize",
+            deserialize = "N: Scalar + serde::Deserialize<'de>"
+        )
+    )
+)]
+pub struct PointData<N = f64> {
+    pos: (Meter<N>, Meter<N>),
+    rot: Angle<N>,
+}
+
+/// Serialized form of `PointData`, with positions as plain meters.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct RawPointData<N> {
+    x: N,
+    y: N,
+    rot: N,
+}
+
+#[cfg(feature = "serde")]
+impl<N: Scalar> From<PointData<N>> for RawPointData<N> {
+    #[inline]
+    fn from(p: PointData<N>) -> Self {
+        Self {
+            x: meters(p.pos.0),
+            y: meters(p.pos.1),
+            rot: p.rot.radians(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<N: Scalar> From<RawPointData<N>> for PointData<N> {
+    #[inline]
+    fn from(r: RawPointData<N>) -> Self {
+        PointData::xyr(r.x, r.y, Angle::from_radians(r.rot))
+    }
 }
 
 #[macro_export]
@@ -34,9 +202,9 @@ macro_rules! const_unit {
     };
 }
 
-impl PointData {
+impl<N: Scalar> PointData<N> {
     #[inline]
-    pub const fn xyr(x: f64, y: f64, r: f64) -> Self {
+    pub const fn xyr(x: N, y: N, r: Angle<N>) -> Self {
         Self {
             pos: (const_unit!(x), const_unit!(y)),
             rot: r,
@@ -46,47 +214,54 @@ impl PointData {
     #[inline]
     pub(crate) fn inverse_relative_to(&self, other: Self) -> Self {
         // what do I need to transform other to to get where I am
-        let s = self.pos;
+        let s = (meters(self.pos.0), meters(self.pos.1));
         let t = other.rot;
         Self {
             pos: (
-                s.0 * t.cos() - s.1 * t.sin() + other.pos.0,
-                s.0 * t.sin() + s.1 * t.cos() + other.pos.1,
+                const_unit!(s.0 * t.cos() - s.1 * t.sin() + meters(other.pos.0)),
+                const_unit!(s.0 * t.sin() + s.1 * t.cos() + meters(other.pos.1)),
             ),
             rot: self.rot + other.rot,
         }
     }
 
+    /// The pose at `offset`, given in the frame this pose defines, expressed in this pose's own
+    /// frame. Chaining offsets this way walks forward from a pose, turning with it.
+    #[inline]
+    pub fn compose(&self, offset: Self) -> Self {
+        offset.inverse_relative_to(*self)
+    }
+
     #[inline]
     pub(crate) fn invert_parent_child_relation(&self) -> Self {
-        let s = self.pos;
+        let s = (meters(self.pos.0), meters(self.pos.1));
         let t = -self.rot;
         Self {
             pos: (
-                -s.0 * t.cos() + s.1 * t.sin(),
-                -s.0 * t.sin() - s.1 * t.cos(),
+                const_unit!(-s.0 * t.cos() + s.1 * t.sin()),
+                const_unit!(-s.0 * t.sin() - s.1 * t.cos()),
             ),
             rot: t,
         }
     }
 
     #[inline]
-    pub fn x(&self) -> Meter {
+    pub fn x(&self) -> Meter<N> {
         self.pos.0
     }
 
     #[inline]
-    pub fn y(&self) -> Meter {
+    pub fn y(&self) -> Meter<N> {
         self.pos.1
     }
 
     #[inline]
-    pub fn pos(&self) -> (Meter, Meter) {
+    pub fn pos(&self) -> (Meter<N>, Meter<N>) {
         self.pos
     }
 
     #[inline]
-    pub fn rot(&self) -> Radians {
+    pub fn rot(&self) -> Angle<N> {
         self.rot
     }
 
@@ -94,33 +269,36 @@ impl PointData {
     pub fn mirror(&self, axis: Axis) -> Self {
         match axis {
             Axis::X => Self {
-                pos: (self.pos.0, -self.pos.1),
+                pos: (self.pos.0, const_unit!(-meters(self.pos.1))),
                 rot: -self.rot,
             },
             Axis::Y => Self {
-                pos: (-self.pos.0, self.pos.1),
-                rot: std::f64::consts::PI - self.rot,
+                pos: (const_unit!(-meters(self.pos.0)), self.pos.1),
+                rot: Angle::from_radians(N::PI()) - self.rot,
             },
         }
     }
 }
 
-impl std::ops::Add for PointData {
-    type Output = PointData;
-    fn add(self, rhs: PointData) -> PointData {
+impl<N: Scalar> std::ops::Add for PointData<N> {
+    type Output = PointData<N>;
+    fn add(self, rhs: PointData<N>) -> PointData<N> {
         Self {
-            pos: (self.pos.0 + rhs.pos.0, self.pos.1 + rhs.pos.1),
+            pos: (
+                const_unit!(meters(self.pos.0) + meters(rhs.pos.0)),
+                const_unit!(meters(self.pos.1) + meters(rhs.pos.1)),
+            ),
             rot: self.rot + rhs.rot,
         }
     }
 }
 
-impl Default for PointData {
+impl<N: Scalar> Default for PointData<N> {
     #[inline]
     fn default() -> Self {
         Self {
-            pos: (0. * si::M, 0. * si::M),
-            rot: 0.,
+            pos: (const_unit!(N::zero()), const_unit!(N::zero())),
+            rot: Angle::default(),
         }
     }
 }
@@ -128,6 +306,7 @@ impl Default for PointData {
 #[cfg(test)]
 mod point_data_test {
     use super::*;
+    use dim::si;
     extern crate rand;
     use self::rand::{distributions::Uniform, Rng, SeedableRng, XorShiftRng};
 
@@ -135,11 +314,11 @@ mod point_data_test {
     fn frame_relative_tos() {
         let p = PointData {
             pos: (1.7 * si::M, -1.5 * si::M),
-            rot: 0.73,
+            rot: Angle::from_radians(0.73),
         };
         let frame = PointData {
             pos: (-1.6 * si::M, 0.33 * si::M),
-            rot: 0.27,
+            rot: Angle::from_radians(0.27),
         };
 
         let prime = p.inverse_relative_to(frame);
@@ -147,6 +326,40 @@ mod point_data_test {
         assert_approx_eq!(prime.pos.1 / si::M, -0.6622, 1e-4);
     }
 
+    #[test]
+    fn single_precision() {
+        let p = PointData::<f32>::xyr(1.7, -1.5, Angle::from_radians(0.73));
+        let frame = PointData::<f32>::xyr(-1.6, 0.33, Angle::from_radians(0.27));
+
+        let prime = p.inverse_relative_to(frame);
+        assert_approx_eq!(meters(prime.x()), 0.4385f32, 1e-4);
+        assert_approx_eq!(meters(prime.y()), -0.6622f32, 1e-4);
+
+        let back = prime.inverse_relative_to(frame.invert_parent_child_relation());
+        assert_approx_eq!(meters(back.x()), 1.7f32, 1e-4);
+        assert_approx_eq!(meters(back.y()), -1.5f32, 1e-4);
+        assert_approx_eq!(back.rot().radians(), 0.73f32, 1e-4);
+    }
+
+    #[test]
+    fn compose() {
+        use std::f64::consts::PI;
+        let p = PointData::xyr(1., 2., Angle::from_radians(PI / 2.));
+        let q = p.compose(PointData::xyr(3., 0.5, Angle::from_radians(PI / 4.)));
+        assert_approx_eq!(meters(q.x()), 0.5);
+        assert_approx_eq!(meters(q.y()), 5.);
+        assert_approx_eq!(q.rot().radians(), 3. * PI / 4.);
+
+        // composing with the inverse goes back to where you started
+        let frame = PointData::xyr(-1.6, 0.33, Angle::from_radians(0.27));
+        let back = p
+            .compose(frame)
+            .compose(frame.invert_parent_child_relation());
+        assert_approx_eq!(meters(back.x()), 1.);
+        assert_approx_eq!(meters(back.y()), 2.);
+        assert_approx_eq!(back.rot().distance(p.rot()), 0.);
+    }
+
     #[test]
     fn invert_parent_child_relation() {
         fn test_point(f: PointData) {
@@ -155,7 +368,7 @@ mod point_data_test {
                 .invert_parent_child_relation();
             assert_approx_eq!(fprime.pos.0 / si::M, f.pos.0 / si::M, 1e-4);
             assert_approx_eq!(fprime.pos.1 / si::M, f.pos.1 / si::M, 1e-4);
-            assert_approx_eq!(fprime.rot, f.rot, 1e-4);
+            assert_approx_eq!(fprime.rot.distance(f.rot), 0., 1e-4);
             // println!("Double inversion passed for {:?}", f);
         }
 
@@ -167,7 +380,7 @@ mod point_data_test {
         for _ in 0..50000 {
             test_point(PointData {
                 pos: (s() * si::M, s() * si::M),
-                rot: s(),
+                rot: Angle::from_radians(s()),
             });
         }
     }
@@ -180,7 +393,7 @@ mod point_data_test {
                 .inverse_relative_to(frame.invert_parent_child_relation());
             assert_approx_eq!(prime.pos.0 / si::M, p.pos.0 / si::M, 1e-4);
             assert_approx_eq!(prime.pos.1 / si::M, p.pos.1 / si::M, 1e-4);
-            assert_approx_eq!(prime.rot, p.rot, 1e-4);
+            assert_approx_eq!(prime.rot.distance(p.rot), 0., 1e-4);
             // println!("Inverse relative to reversal passed for {:?}", p);
         }
 
@@ -192,7 +405,7 @@ mod point_data_test {
                 .inverse_relative_to(frame.invert_parent_child_relation());
             assert_approx_eq!(prime.pos.0 / si::M, p.pos.0 / si::M, 1e-4);
             assert_approx_eq!(prime.pos.1 / si::M, p.pos.1 / si::M, 1e-4);
-            assert_approx_eq!(prime.rot, p.rot, 1e-4);
+            assert_approx_eq!(prime.rot.distance(p.rot), 0., 1e-4);
             // println!("Inverse relative to reversal passed for {:?}", p);
         }
 
@@ -205,31 +418,75 @@ mod point_data_test {
             test_point(
                 PointData {
                     pos: (s() * si::M, s() * si::M),
-                    rot: s(),
+                    rot: Angle::from_radians(s()),
                 },
                 PointData {
                     pos: (s() * si::M, s() * si::M),
-                    rot: s(),
+                    rot: Angle::from_radians(s()),
                 },
             );
             test_point_2(
                 PointData {
                     pos: (s() * si::M, s() * si::M),
-                    rot: s(),
+                    rot: Angle::from_radians(s()),
                 },
                 PointData {
                     pos: (s() * si::M, s() * si::M),
-                    rot: s(),
+                    rot: Angle::from_radians(s()),
                 },
                 PointData {
                     pos: (s() * si::M, s() * si::M),
-                    rot: s(),
+                    rot: Angle::from_radians(s()),
                 },
             );
         }
     }
 }
 
+#[cfg(test)]
+mod angle_test {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn wraps() {
+        assert_approx_eq!(Angle::from_radians(3. * PI / 2.).radians(), -PI / 2.);
+        assert_approx_eq!(Angle::from_radians(-3. * PI / 2.).radians(), PI / 2.);
+        assert_approx_eq!(Angle::from_radians(-PI).radians(), PI);
+        assert_approx_eq!(Angle::from_radians(PI).radians(), PI);
+        assert_approx_eq!(Angle::from_radians(20. * PI + 0.1).radians(), 0.1);
+        assert_approx_eq!(Angle::from_degrees(270.).degrees(), -90.);
+        assert_approx_eq!(Angle::from_degrees(90.).radians(), PI / 2.);
+    }
+
+    #[test]
+    fn shortest_diff() {
+        let a = Angle::from_degrees(170.);
+        let b = Angle::from_degrees(-170.);
+        assert_approx_eq!(a.shortest_diff(b).degrees(), 20.);
+        assert_approx_eq!(b.shortest_diff(a).degrees(), -20.);
+        assert_approx_eq!(a.distance(b), 20f64.to_radians());
+        assert_approx_eq!((a + b).radians(), 0.);
+        assert_approx_eq!((a * 2.).degrees(), -20.);
+    }
+
+    #[test]
+    fn normalized_in_constants() {
+        const QUARTER: Angle = Angle::from_normalized_radians(PI / 2.);
+        assert_eq!(QUARTER, Angle::from_radians(PI / 2.));
+        assert_eq!(QUARTER.radians(), PI / 2.);
+    }
+
+    #[test]
+    fn mirror() {
+        let p = PointData::xyr(1., 2., Angle::from_degrees(30.));
+        assert_approx_eq!(p.mirror(Axis::X).rot().degrees(), -30.);
+        assert_approx_eq!(p.mirror(Axis::Y).rot().degrees(), 150.);
+        let p = PointData::xyr(1., 2., Angle::from_degrees(-150.));
+        assert_approx_eq!(p.mirror(Axis::Y).rot().degrees(), -30.);
+    }
+}
+
 pub enum ParentFrame<S: PointHeirarchy + Sized> {
     Root,
     Parent(S),
@@ -285,55 +542,89 @@ pub trait PointHeirarchy: Sized + Copy + Into<usize> + Eq {
     fn order() -> usize;
 }
 
-pub struct FrameRegistry<S: PointHeirarchy>(Vec<PointData>, PhantomData<S>);
+pub struct FrameRegistry<S: PointHeirarchy, N = f64>(Vec<PointData<N>>, PhantomData<S>);
 
-impl<S: PointHeirarchy> FrameRegistry<S> {
+impl<S: PointHeirarchy, N: Scalar> FrameRegistry<S, N> {
     #[inline]
     pub fn new() -> Self {
         Self(vec![PointData::default(); S::order()], PhantomData)
     }
 
     #[inline]
-    pub fn raw_tf_mut(&mut self, frame: S) -> &mut PointData {
+    pub fn raw_tf_mut(&mut self, frame: S) -> &mut PointData<N> {
         &mut self.0[frame.into()]
     }
 
     #[inline]
-    pub fn raw_tf(&self, frame: S) -> PointData {
+    pub fn raw_tf(&self, frame: S) -> PointData<N> {
         self.0[frame.into()]
     }
 
     /// # Panics
     /// If you attempt to set the origin of a frame whose parent is `ParentFrame::Root`.
     #[inline]
-    pub fn set_origin(&mut self, frame: S, p: TfPoint<S>) {
+    pub fn set_origin(&mut self, frame: S, p: TfPoint<S, N>) {
         let data = match frame.parent() {
             ParentFrame::Root => panic!(),
-            ParentFrame::Parent(s) => p.in_frame(&self, s).1,
+            ParentFrame::Parent(s) => p.in_frame(self, s).1,
         };
 
         *self.raw_tf_mut(frame) = data;
     }
 }
 
-impl<S: PointHeirarchy> Default for FrameRegistry<S> {
+impl<S: PointHeirarchy, N: Scalar> Default for FrameRegistry<S, N> {
     #[inline]
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct TfPoint<S: PointHeirarchy>(S, PointData);
+/// Serializes as the list of raw transforms, indexed by frame.
+#[cfg(feature = "serde")]
+impl<S: PointHeirarchy, N: Scalar + serde::Serialize> serde::Serialize for FrameRegistry<S, N> {
+    fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        self.0.serialize(serializer)
+    }
+}
 
-impl<S: PointHeirarchy> TfPoint<S> {
+/// # Errors
+/// If the number of transforms does not match `S::order()`.
+#[cfg(feature = "serde")]
+impl<'de, S: PointHeirarchy, N: Scalar + serde::Deserialize<'de>> serde::Deserialize<'de>
+    for FrameRegistry<S, N>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tfs = Vec::<PointData<N>>::deserialize(deserializer)?;
+        if tfs.len() != S::order() {
+            return Err(serde::de::Error::invalid_length(
+                tfs.len(),
+                &"one transform per frame",
+            ));
+        }
+        Ok(FrameRegistry(tfs, PhantomData))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "S: serde::Serialize, N: Scalar + serde::Serialize",
+        deserialize = "S: serde::Deserialize<'de>, N: Scalar + serde::Deserialize<'de>"
+    ))
+)]
+pub struct TfPoint<S: PointHeirarchy, N = f64>(S, PointData<N>);
+
+impl<S: PointHeirarchy, N: Scalar> TfPoint<S, N> {
     #[inline]
-    pub const fn new(frame: S, x: Meter, y: Meter, rot: Radians) -> Self {
+    pub const fn new(frame: S, x: Meter<N>, y: Meter<N>, rot: Angle<N>) -> Self {
         Self(frame, PointData { pos: (x, y), rot })
     }
 
     #[inline]
-    pub fn in_frame(&self, register: &FrameRegistry<S>, frame: S) -> Self {
+    pub fn in_frame(&self, register: &FrameRegistry<S, N>, frame: S) -> Self {
         let (up, down) = self.0.path_to(frame);
         let mut result = self.1;
         up.iter()
@@ -345,12 +636,12 @@ impl<S: PointHeirarchy> TfPoint<S> {
     }
 
     #[inline]
-    pub fn from_raw(raw: PointData, frame: S) -> Self {
+    pub fn from_raw(raw: PointData<N>, frame: S) -> Self {
         Self(frame, raw)
     }
 
     #[inline]
-    pub fn raw_data(&self) -> PointData {
+    pub fn raw_data(&self) -> PointData<N> {
         self.1
     }
 
@@ -363,12 +654,18 @@ impl<S: PointHeirarchy> TfPoint<S> {
     pub fn mirror(&self, axis: Axis) -> Self {
         Self::from_raw(self.1.mirror(axis), self.0)
     }
+
+    /// Moves by `offset`, measured along this point's heading, staying in the same frame.
+    #[inline]
+    pub fn compose(&self, offset: PointData<N>) -> Self {
+        Self::from_raw(self.1.compose(offset), self.0)
+    }
 }
 
-impl<S: PointHeirarchy> std::ops::Add<PointData> for TfPoint<S> {
+impl<S: PointHeirarchy, N: Scalar> std::ops::Add<PointData<N>> for TfPoint<S, N> {
     type Output = Self;
     #[inline]
-    fn add(self, rhs: PointData) -> TfPoint<S> {
+    fn add(self, rhs: PointData<N>) -> TfPoint<S, N> {
         TfPoint::from_raw(self.1 + rhs, self.0)
     }
 }
@@ -376,8 +673,10 @@ impl<S: PointHeirarchy> std::ops::Add<PointData> for TfPoint<S> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use dim::si;
     // TODO macroify this and the impl trait
     #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     #[repr(usize)]
     pub enum PathFrames {
         Robot,
@@ -436,35 +735,79 @@ mod test {
         assert!(a.0 == b.0);
         assert_approx_eq!(a.1.pos.0 / si::M, b.1.pos.0 / si::M);
         assert_approx_eq!(a.1.pos.1 / si::M, b.1.pos.1 / si::M);
-        assert_approx_eq!(a.1.rot, b.1.rot);
+        assert_approx_eq!(a.1.rot.distance(b.1.rot), 0.);
+    }
+
+    #[test]
+    fn const_points() {
+        const SCALE: TfPoint<PathFrames> = TfPoint::new(
+            PathFrames::Scale,
+            const_unit!(5.),
+            const_unit!(0.),
+            Angle::from_normalized_radians(0.321),
+        );
+        near_eq(
+            SCALE,
+            TfPoint::new(
+                PathFrames::Scale,
+                5. * si::M,
+                0. * si::M,
+                Angle::from_radians(0.321),
+            ),
+        );
     }
 
     #[test]
     fn raw_rooted_transforms() {
         let mut reg = FrameRegistry::<PathFrames>::new();
 
-        let p = TfPoint::new(PathFrames::Scale, 5. * si::M, 0. * si::M, 0.321);
-        let f = TfPoint::new(PathFrames::Switch, -1. * si::M, 2. * si::M, 0.787);
+        let p = TfPoint::new(
+            PathFrames::Scale,
+            5. * si::M,
+            0. * si::M,
+            Angle::from_radians(0.321),
+        );
+        let f = TfPoint::new(
+            PathFrames::Switch,
+            -1. * si::M,
+            2. * si::M,
+            Angle::from_radians(0.787),
+        );
 
         // zero rotation
-        *reg.raw_tf_mut(PathFrames::Scale) = PointData::xyr(10., 3., 0.);
-        *reg.raw_tf_mut(PathFrames::Switch) = PointData::xyr(-5., 7., 0.);
+        *reg.raw_tf_mut(PathFrames::Scale) = PointData::xyr(10., 3., Angle::from_radians(0.));
+        *reg.raw_tf_mut(PathFrames::Switch) = PointData::xyr(-5., 7., Angle::from_radians(0.));
         near_eq(
             p.in_frame(&reg, PathFrames::Field),
-            TfPoint::new(PathFrames::Field, 15. * si::M, 3.0 * si::M, 0.321),
+            TfPoint::new(
+                PathFrames::Field,
+                15. * si::M,
+                3.0 * si::M,
+                Angle::from_radians(0.321),
+            ),
         );
         near_eq(
             f.in_frame(&reg, PathFrames::Scale),
-            TfPoint::new(PathFrames::Scale, -16. * si::M, 6.0 * si::M, 0.787),
+            TfPoint::new(
+                PathFrames::Scale,
+                -16. * si::M,
+                6.0 * si::M,
+                Angle::from_radians(0.787),
+            ),
         );
 
         // axis aligned rotation
         use std::f64::consts::PI;
-        *reg.raw_tf_mut(PathFrames::Scale) = PointData::xyr(10., 3., PI);
-        *reg.raw_tf_mut(PathFrames::Switch) = PointData::xyr(-5., 7., PI / 2.);
+        *reg.raw_tf_mut(PathFrames::Scale) = PointData::xyr(10., 3., Angle::from_radians(PI));
+        *reg.raw_tf_mut(PathFrames::Switch) = PointData::xyr(-5., 7., Angle::from_radians(PI / 2.));
         near_eq(
             p.in_frame(&reg, PathFrames::Field),
-            TfPoint::new(PathFrames::Field, 5. * si::M, 3.0 * si::M, 0.321 + PI),
+            TfPoint::new(
+                PathFrames::Field,
+                5. * si::M,
+                3.0 * si::M,
+                Angle::from_radians(0.321 + PI),
+            ),
         );
         near_eq(
             f.in_frame(&reg, PathFrames::Scale),
@@ -472,7 +815,7 @@ mod test {
                 PathFrames::Scale,
                 17. * si::M,
                 -3. * si::M,
-                -(PI / 2. - 0.787),
+                Angle::from_radians(-(PI / 2. - 0.787)),
             ),
         );
     }
@@ -481,32 +824,117 @@ mod test {
     fn transforms() {
         let mut reg = FrameRegistry::<PathFrames>::new();
 
-        let p = TfPoint::new(PathFrames::Scale, 5. * si::M, 0. * si::M, 0.321);
-        let f = TfPoint::new(PathFrames::Switch, -1. * si::M, 2. * si::M, 0.787);
+        let p = TfPoint::new(
+            PathFrames::Scale,
+            5. * si::M,
+            0. * si::M,
+            Angle::from_radians(0.321),
+        );
+        let f = TfPoint::new(
+            PathFrames::Switch,
+            -1. * si::M,
+            2. * si::M,
+            Angle::from_radians(0.787),
+        );
 
         // zero rotation
         // reg.set_tf_from_parent(PathFrames::Scale, PointData::xyr(10., 3., 0.));
         // reg.set_tf_from_parent(PathFrames::Switch, PointData::xyr(-5., 7., 0.));
         reg.set_origin(
             PathFrames::Scale,
-            TfPoint::new(PathFrames::Field, 10. * si::M, 3. * si::M, 0.),
+            TfPoint::new(
+                PathFrames::Field,
+                10. * si::M,
+                3. * si::M,
+                Angle::from_radians(0.),
+            ),
         );
 
         reg.set_origin(
             PathFrames::Switch,
-            TfPoint::new(PathFrames::Scale, -15. * si::M, 4. * si::M, 0.),
+            TfPoint::new(
+                PathFrames::Scale,
+                -15. * si::M,
+                4. * si::M,
+                Angle::from_radians(0.),
+            ),
         );
         near_eq(
             p.in_frame(&reg, PathFrames::Field),
-            TfPoint::new(PathFrames::Field, 15. * si::M, 3.0 * si::M, 0.321),
+            TfPoint::new(
+                PathFrames::Field,
+                15. * si::M,
+                3.0 * si::M,
+                Angle::from_radians(0.321),
+            ),
         );
         near_eq(
             f.in_frame(&reg, PathFrames::Scale),
-            TfPoint::new(PathFrames::Scale, -16. * si::M, 6.0 * si::M, 0.787),
+            TfPoint::new(
+                PathFrames::Scale,
+                -16. * si::M,
+                6.0 * si::M,
+                Angle::from_radians(0.787),
+            ),
         );
 
         // TODO(Lytigas): axis aligned rotation
         // TODO(Lytigas): non axis aligned tests
         // TODO(Lytigas): tests between different frames that have root parents
     }
+
+    #[test]
+    fn compose_matches_child_frame() {
+        // a point offset from a frame's origin is the same as that point in the frame
+        let mut reg = FrameRegistry::<PathFrames>::new();
+        let origin = TfPoint::new(
+            PathFrames::Field,
+            10. * si::M,
+            3. * si::M,
+            Angle::from_radians(2.1),
+        );
+        reg.set_origin(PathFrames::Switch, origin);
+        let offset = PointData::xyr(-0.5, 1.25, Angle::from_radians(-0.4));
+        near_eq(
+            origin.compose(offset),
+            TfPoint::from_raw(offset, PathFrames::Switch).in_frame(&reg, PathFrames::Field),
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        extern crate serde_json;
+
+        let p = TfPoint::new(
+            PathFrames::Scale,
+            5. * si::M,
+            -1.25 * si::M,
+            Angle::from_radians(0.321),
+        );
+        let json = serde_json::to_string(&p).unwrap();
+        let back: TfPoint<PathFrames> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.frame(), p.frame());
+        assert_eq!(back.raw_data(), p.raw_data());
+
+        let mut reg = FrameRegistry::<PathFrames>::new();
+        *reg.raw_tf_mut(PathFrames::Scale) = PointData::xyr(10., 3., Angle::from_radians(0.5));
+        *reg.raw_tf_mut(PathFrames::CubeDepo) = PointData::xyr(-1., 0.25, Angle::from_radians(-2.));
+        let json = serde_json::to_string(&reg).unwrap();
+        let back: FrameRegistry<PathFrames> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            back.raw_tf(PathFrames::Scale),
+            reg.raw_tf(PathFrames::Scale)
+        );
+        assert_eq!(
+            back.raw_tf(PathFrames::CubeDepo),
+            reg.raw_tf(PathFrames::CubeDepo)
+        );
+
+        // a registry snapshot for a different heirarchy must not load
+        assert!(
+            serde_json::from_str::<FrameRegistry<PathFrames>>(r#"[{"x":0,"y":0,"rot":0}]"#)
+                .is_err()
+        );
+    }
 }
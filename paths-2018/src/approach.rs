@@ -0,0 +1,145 @@
+use coord_frames::*;
+use dim::si;
+use serde::{Deserialize, Serialize};
+
+/// The robot's outline, bumpers included.
+#[derive(Debug, Copy, Clone)]
+pub struct Footprint {
+    /// Front to back.
+    pub length: Meter,
+    /// Side to side.
+    pub width: Meter,
+}
+
+/// Which face of the robot goes against a target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Side {
+    Front,
+    Back,
+    Left,
+    Right,
+}
+
+impl Footprint {
+    /// The pose of the robot's center that puts the middle of `side` square against `target`,
+    /// `standoff` short of it. `target` points the way the robot has to move to reach it, so for
+    /// a wall it is on the wall's face and heads into the wall.
+    pub fn approach<S: PointHeirarchy>(
+        &self,
+        target: TfPoint<S>,
+        side: Side,
+        standoff: Meter,
+    ) -> TfPoint<S> {
+        let (depth, turn) = match side {
+            Side::Front => (self.length, 0.),
+            Side::Back => (self.length, 180.),
+            Side::Left => (self.width, -90.),
+            Side::Right => (self.width, 90.),
+        };
+        target.compose(PointData::xyr(
+            -*((depth / 2. + standoff) / si::M),
+            0.,
+            Angle::from_degrees(turn),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+    use crate::frames::PathFrame;
+    use crate::FEET_PER_METER;
+    use std::path::Path;
+
+    /// The robot the starting poses were placed for before they were worked out from it.
+    const LENGTH: f64 = 0.9525;
+    const WIDTH: f64 = 0.8636;
+
+    fn robot() -> Footprint {
+        Footprint {
+            length: LENGTH * si::M,
+            width: WIDTH * si::M,
+        }
+    }
+
+    fn target(x: f64, y: f64, heading: f64) -> TfPoint<PathFrame> {
+        TfPoint::new(
+            PathFrame::Field,
+            x * si::M,
+            y * si::M,
+            Angle::from_degrees(heading),
+        )
+    }
+
+    fn assert_pose(pose: TfPoint<PathFrame>, x: f64, y: f64, heading: f64) {
+        let pose = pose.raw_data();
+        assert!((*(pose.x() / si::M) - x).abs() < 1e-6, "{:?}", pose);
+        assert!((*(pose.y() / si::M) - y).abs() < 1e-6, "{:?}", pose);
+        let turn = pose.rot().shortest_diff(Angle::from_degrees(heading));
+        assert!(turn.radians().abs() < 1e-6, "{:?}", pose);
+    }
+
+    #[test]
+    fn each_side_against_the_target() {
+        let robot = robot();
+        let at = |side| robot.approach(target(0., 0., 0.), side, 0. * si::M);
+        assert_pose(at(Side::Front), -LENGTH / 2., 0., 0.);
+        assert_pose(at(Side::Back), -LENGTH / 2., 0., 180.);
+        // turned so the left face heads into the target
+        assert_pose(at(Side::Left), -WIDTH / 2., 0., -90.);
+        assert_pose(at(Side::Right), -WIDTH / 2., 0., 90.);
+    }
+
+    #[test]
+    fn stands_off_along_the_target() {
+        let pose = robot().approach(target(0., 0., 0.), Side::Front, 0.254 * si::M);
+        assert_pose(pose, -LENGTH / 2. - 0.254, 0., 0.);
+    }
+
+    #[test]
+    fn follows_a_rotated_target() {
+        let robot = robot();
+        let target = target(1., 2., 90.);
+        assert_pose(
+            robot.approach(target, Side::Front, 0.1 * si::M),
+            1.,
+            2. - LENGTH / 2. - 0.1,
+            90.,
+        );
+        assert_pose(
+            robot.approach(target, Side::Right, 0. * si::M),
+            1.,
+            2. - WIDTH / 2.,
+            180.,
+        );
+    }
+
+    #[test]
+    fn matches_the_hand_placed_starts() {
+        let field = Field::load(Path::new("fields/2018.json")).unwrap();
+        let frames = field.frames().unwrap();
+        let robot = robot();
+        // back to the wall, a corner on the element's end
+        let start = |frame, y: f64| {
+            let wall = TfPoint::new(frame, 0. * si::M, y * si::M, Angle::from_degrees(180.));
+            let start = robot.approach(wall, Side::Back, 0. * si::M);
+            start.in_frame(&frames, PathFrame::Field)
+        };
+        // where the starts were, in feet to the precision they were printed with
+        let feet = |ft: f64| ft / FEET_PER_METER;
+        assert_pose(
+            start(PathFrame::Exchange, -WIDTH / 2.),
+            feet(0.41666668),
+            feet(1.5625),
+            90.,
+        );
+        assert_pose(
+            start(PathFrame::DiamondPlateEnd, WIDTH / 2.),
+            feet(9.58333364),
+            feet(1.5625),
+            90.,
+        );
+    }
+}
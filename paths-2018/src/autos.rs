@@ -0,0 +1,376 @@
+use crate::approach::Side;
+use crate::curve::Spline;
+use crate::field::{Field, FieldError};
+use crate::frames::PathFrame;
+use crate::path::{MarkerPosition, PathChain, Segment};
+use crate::robot::Robot;
+use crate::FEET_PER_METER;
+use coord_frames::*;
+use dim::si;
+use eta3_spline::*;
+
+/// Every pose and path generated for the 2018 game, in the order they are defined.
+pub struct Autos {
+    pub poses: Vec<(String, PointData)>,
+    pub paths: Vec<(String, PathChain)>,
+}
+
+impl Autos {
+    fn pose(&mut self, name: &str, pose: PointData) {
+        self.poses.push((name.to_owned(), pose));
+    }
+
+    fn path(
+        &mut self,
+        points: Vec<MotionState<f64>>,
+        spline: impl Into<Spline>,
+        name: &str,
+        num_pts: usize,
+    ) {
+        self.chain(
+            PathChain::new(Segment::forward(points, spline, num_pts)),
+            name,
+        );
+    }
+
+    fn chain(&mut self, chain: PathChain, name: &str) {
+        self.paths.push((name.to_owned(), chain));
+    }
+}
+
+/// The field's frames, with the starting positions placed for the robot's footprint.
+pub fn frames(field: &Field, robot: &Robot) -> Result<FrameRegistry<PathFrame>, FieldError> {
+    let mut frames = field.frames()?;
+    let robot = robot.footprint();
+    let against_wall = |frame, y| {
+        robot.approach(
+            TfPoint::new(frame, 0. * si::M, y, Angle::from_degrees(180.)),
+            Side::Back,
+            0. * si::M,
+        )
+    };
+
+    // the robot starts with its back to the wall, its corner on the element's end
+    frames.set_origin(
+        PathFrame::CenterStart,
+        against_wall(PathFrame::Exchange, -robot.width / 2.),
+    );
+    frames.set_origin(
+        PathFrame::SideStart,
+        against_wall(PathFrame::DiamondPlateEnd, robot.width / 2.),
+    );
+    Ok(frames)
+}
+
+/// Builds everything on `field` for `robot`, failing if an element the autos rely on is missing.
+pub fn define(field: &Field, robot: &Robot) -> Result<Autos, FieldError> {
+    let mut autos = Autos {
+        poses: Vec::new(),
+        paths: Vec::new(),
+    };
+
+    let frames = frames(field, robot)?;
+    let footprint = robot.footprint();
+    let meters = |m: f64| m * si::M;
+    let degrees = Angle::from_degrees;
+    let against = |frame, x, y, heading, side, standoff| {
+        footprint.approach(TfPoint::new(frame, x, y, heading), side, standoff)
+    };
+    let on_field = |p: TfPoint<PathFrame>| p.in_frame(&frames, PathFrame::Field);
+    let switch_plate = field.element("rightSwitchPlate")?;
+    let scale_plate = field.element("rightScalePlate")?;
+
+    // create all things on the right, mirror for the left
+    let center_start = on_field(PathFrame::CenterStart.origin());
+    let side_start = on_field(PathFrame::SideStart.origin());
+    let lane = on_field(PathFrame::SwitchLane.origin());
+
+    autos.pose("centerStart", center_start.raw_data());
+    autos.pose("rightSideStart", side_start.raw_data());
+    autos.pose("leftSideStart", side_start.raw_data().mirror(Axis::Y));
+
+    // bumpers against the near side of the plate, facing it
+    let near_switch = on_field(against(
+        PathFrame::SwitchPlate,
+        -switch_plate.length() / 2.,
+        meters(0.),
+        degrees(0.),
+        Side::Front,
+        meters(0.),
+    ));
+    let far_switch = near_switch.mirror(Axis::Y);
+
+    // near side switch but approaches from the axis of the switch
+    let switch_outside = against(
+        PathFrame::SwitchPlate,
+        meters(0.),
+        -switch_plate.width() / 2.,
+        degrees(90.),
+        Side::Front,
+        meters(0.),
+    );
+    let near_switch_outside = on_field(switch_outside);
+    // in the lane, far enough short of the switch to turn into it on a quarter circle
+    let beside_switch = switch_outside
+        .in_frame(&frames, PathFrame::SwitchLane)
+        .raw_data();
+    let below_switch = on_field(TfPoint::new(
+        PathFrame::SwitchLane,
+        beside_switch.x() - beside_switch.y(),
+        meters(0.),
+        degrees(0.),
+    ));
+
+    // faces from the outside looking in, turned a little toward the middle of the plate
+    let near_scale = on_field(
+        against(
+            PathFrame::ScalePlate,
+            meters(-0.1),
+            -scale_plate.width() / 2.,
+            degrees(90.),
+            Side::Front,
+            meters(0.254),
+        )
+        .compose(PointData::xyr(0., 0., Angle::from_radians(0.254))),
+    );
+    // comes in facing forward, short of the outside corner of the other plate
+    let far_scale = on_field(
+        against(
+            PathFrame::ScalePlate,
+            -scale_plate.length() / 2.,
+            -scale_plate.width() / 2.,
+            degrees(0.),
+            Side::Front,
+            meters(0.4),
+        )
+        .compose(PointData::xyr(0., 0., degrees(12.6))),
+    )
+    .mirror(Axis::Y);
+
+    let mut gen_center_switch = || {
+        autos.path(
+            vec![
+                zero_kappa(center_start.raw_data()),
+                zero_kappa(far_switch.raw_data()),
+            ],
+            vec![basic_param(10.0)],
+            "centerToLeftSwitch",
+            300,
+        );
+        autos.path(
+            vec![
+                zero_kappa(center_start.raw_data()),
+                zero_kappa(near_switch.raw_data()),
+            ],
+            vec![basic_param(10.0)],
+            "centerToRightSwitch",
+            300,
+        );
+    };
+    gen_center_switch();
+
+    let mut gen_side_scale = || {
+        use self::Axis::Y;
+        // near scales
+        autos.chain(
+            PathChain::new(Segment::forward(
+                vec![
+                    zero_kappa(side_start.raw_data()),
+                    with_kappa(near_scale.raw_data(), 2., 0.),
+                ],
+                vec![EtaParam::new(5., 10.0, 0., 25., 0., 0.)],
+                400,
+            ))
+            .with_marker("raiseElevator", MarkerPosition::DistanceFromEnd(1. * si::M)),
+            "rightToRightScale",
+        );
+        autos.chain(
+            PathChain::new(Segment::forward(
+                vec![
+                    zero_kappa(side_start.raw_data().mirror(Y)),
+                    zero_kappa(near_scale.raw_data().mirror(Y)),
+                ],
+                vec![EtaParam::new(5., 10.0, 0., 25., 0., 0.)],
+                400,
+            ))
+            .with_marker("raiseElevator", MarkerPosition::DistanceFromEnd(1. * si::M)),
+            "leftToLeftScale",
+        );
+        // far scales
+        autos.path(
+            vec![
+                zero_kappa(side_start.raw_data()),
+                zero_kappa(lane.raw_data()),
+                with_kappa(far_scale.raw_data(), 0., 0.),
+            ],
+            vec![basic_param(5.0), EtaParam::new(15.0, 12.5, 0., 0., 0., 0.)],
+            "rightToLeftScale",
+            400,
+        );
+        autos.path(
+            vec![
+                zero_kappa(side_start.raw_data().mirror(Y)),
+                zero_kappa(lane.raw_data().mirror(Y)),
+                with_kappa(far_scale.raw_data().mirror(Y), 0., 0.),
+            ],
+            vec![basic_param(5.0), EtaParam::new(15.0, 12.5, 0., 0., 0., 0.)],
+            "leftToRightScale",
+            400,
+        );
+    };
+    gen_side_scale();
+
+    // near switch
+    let mut gen_near_switch = || {
+        use self::Axis::Y;
+        autos.path(
+            vec![
+                zero_kappa(side_start.raw_data()),
+                zero_kappa(near_switch_outside.raw_data()),
+            ],
+            vec![basic_param(10.0)],
+            "rightToRightSwitch",
+            300,
+        );
+        autos.path(
+            vec![
+                zero_kappa(side_start.raw_data().mirror(Y)),
+                zero_kappa(near_switch_outside.raw_data().mirror(Y)),
+            ],
+            vec![basic_param(10.0)],
+            "leftToLeftSwitch",
+            300,
+        );
+    };
+    gen_near_switch();
+
+    // score on the near scale, then back away from it into the lane along the wall
+    let mut gen_scale_back_up = || {
+        use self::Axis::Y;
+        autos.chain(
+            PathChain::new(Segment::forward(
+                vec![
+                    zero_kappa(side_start.raw_data()),
+                    with_kappa(near_scale.raw_data(), 2., 0.),
+                ],
+                vec![EtaParam::new(5., 10.0, 0., 25., 0., 0.)],
+                400,
+            ))
+            .then(Segment::reverse(
+                vec![
+                    zero_kappa(near_scale.raw_data()),
+                    zero_kappa(lane.raw_data()),
+                ],
+                vec![basic_param(5.0)],
+                300,
+            ))
+            .with_marker("raiseElevator", MarkerPosition::Distance(3. * si::M))
+            .with_marker(
+                "eject",
+                MarkerPosition::Waypoint {
+                    segment: 0,
+                    index: 1,
+                },
+            )
+            .with_marker("lowerElevator", MarkerPosition::Fraction(0.75)),
+            "rightScaleBackUp",
+        );
+        autos.chain(
+            PathChain::new(Segment::forward(
+                vec![
+                    zero_kappa(side_start.raw_data().mirror(Y)),
+                    zero_kappa(near_scale.raw_data().mirror(Y)),
+                ],
+                vec![EtaParam::new(5., 10.0, 0., 25., 0., 0.)],
+                400,
+            ))
+            .then(Segment::reverse(
+                vec![
+                    zero_kappa(near_scale.raw_data().mirror(Y)),
+                    zero_kappa(lane.raw_data().mirror(Y)),
+                ],
+                vec![basic_param(5.0)],
+                300,
+            ))
+            .with_marker("raiseElevator", MarkerPosition::Distance(3. * si::M))
+            .with_marker(
+                "eject",
+                MarkerPosition::Waypoint {
+                    segment: 0,
+                    index: 1,
+                },
+            )
+            .with_marker("lowerElevator", MarkerPosition::Fraction(0.75)),
+            "leftScaleBackUp",
+        );
+    };
+    gen_scale_back_up();
+
+    // from the near scale, back down the lane past the switch and turn into its outside
+    let mut gen_scale_to_switch = || {
+        use self::Axis::Y;
+        autos.chain(
+            PathChain::new(Segment::reverse(
+                vec![
+                    zero_kappa(near_scale.raw_data()),
+                    zero_kappa(below_switch.raw_data()),
+                ],
+                vec![basic_param(15.0)],
+                300,
+            ))
+            .then(Segment::forward(
+                vec![
+                    zero_kappa(below_switch.raw_data()),
+                    zero_kappa(near_switch_outside.raw_data()),
+                ],
+                vec![basic_param(3.0)],
+                200,
+            ))
+            .with_marker("lowerElevator", MarkerPosition::Distance(0.5 * si::M)),
+            "rightScaleToRightSwitch",
+        );
+        autos.chain(
+            PathChain::new(Segment::reverse(
+                vec![
+                    zero_kappa(near_scale.raw_data().mirror(Y)),
+                    zero_kappa(below_switch.raw_data().mirror(Y)),
+                ],
+                vec![basic_param(15.0)],
+                300,
+            ))
+            .then(Segment::forward(
+                vec![
+                    zero_kappa(below_switch.raw_data().mirror(Y)),
+                    zero_kappa(near_switch_outside.raw_data().mirror(Y)),
+                ],
+                vec![basic_param(3.0)],
+                200,
+            ))
+            .with_marker("lowerElevator", MarkerPosition::Distance(0.5 * si::M)),
+            "leftScaleToLeftSwitch",
+        );
+    };
+    gen_scale_to_switch();
+
+    Ok(autos)
+}
+
+pub fn with_kappa(point: PointData, k: f64, dk: f64) -> MotionState<f64> {
+    MotionState {
+        // convert m to feet for the robot
+        x: *(point.x() / si::M) * FEET_PER_METER,
+        y: *(point.y() / si::M) * FEET_PER_METER,
+        t: point.rot().radians(),
+        k,
+        dk,
+    }
+}
+
+fn zero_kappa(point: PointData) -> MotionState<f64> {
+    with_kappa(point, 0., 0.)
+}
+
+fn basic_param(a: f64) -> EtaParam<f64> {
+    assert!(a > 0.0);
+    EtaParam::new(a, a, 0., 0., 0., 0.)
+}
@@ -0,0 +1,143 @@
+//! Generates paths as independent jobs spread across cores. Results come back in the order the
+//! jobs were given, whatever order they finish in, so output and reports don't change from run
+//! to run.
+
+use crate::error::ExportError;
+use crate::validate::Report;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// What generating one path came to.
+pub struct PathOutcome {
+    pub name: String,
+    /// Samples in the path, or 0 if it couldn't be sampled.
+    pub points: usize,
+    /// How it fits the robot's limits and the field, if it got as far as being checked.
+    pub report: Option<Report>,
+    pub failures: Vec<ExportError>,
+    pub elapsed: Duration,
+}
+
+impl PathOutcome {
+    fn status(&self) -> String {
+        match self.failures[..] {
+            [] => match self.report {
+                Some(ref report) if !report.is_ok() => format!(
+                    "ok, {} limits exceeded, {} collisions",
+                    report.violations.len(),
+                    report.collisions.len()
+                ),
+                _ => "ok".to_owned(),
+            },
+            [ref failure] => failure.to_string(),
+            ref failures => format!("{} failures", failures.len()),
+        }
+    }
+}
+
+/// How many jobs to run at once when not told: one per core.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Runs `job` on every item with up to `threads` at once, returning the results in the order of
+/// `items`.
+pub fn run<T, R, F>(items: &[T], threads: usize, job: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = threads.max(1).min(items.len());
+    if threads <= 1 {
+        return items.iter().map(job).collect();
+    }
+
+    // workers take the next item as they free up, so one slow path doesn't hold up a whole share
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match items.get(i) {
+                            Some(item) => done.push((i, job(item))),
+                            None => return done,
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    results.sort_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Prints a line per path with how long it took and whether it made it, then the totals.
+pub fn print_summary(outcomes: &[PathOutcome], threads: usize, wall: Duration) {
+    let width = outcomes
+        .iter()
+        .map(|o| o.name.len())
+        .chain(Some("path".len()))
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{:<width$}  {:>6}  {:>9}  status",
+        "path",
+        "points",
+        "time",
+        width = width
+    );
+    for outcome in outcomes {
+        println!(
+            "{:<width$}  {:>6}  {:>6.1} ms  {}",
+            outcome.name,
+            outcome.points,
+            outcome.elapsed.as_secs_f64() * 1000.,
+            outcome.status(),
+            width = width
+        );
+    }
+    let busy: Duration = outcomes.iter().map(|o| o.elapsed).sum();
+    println!(
+        "{} paths in {:.1} ms on {} threads, {:.1} ms of work",
+        outcomes.len(),
+        wall.as_secs_f64() * 1000.,
+        threads.max(1).min(outcomes.len().max(1)),
+        busy.as_secs_f64() * 1000.
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    #[test]
+    fn results_keep_the_order_of_the_items() {
+        let items: Vec<u64> = (0..12).collect();
+        let finished = Mutex::new(Vec::new());
+        let workers = Mutex::new(HashSet::new());
+        // the early items take longest, so later ones finish first
+        let results = run(&items, 4, |&item| {
+            thread::sleep(Duration::from_millis(5 * (12 - item) * (item % 3 + 1)));
+            finished.lock().unwrap().push(item);
+            workers.lock().unwrap().insert(thread::current().id());
+            item * 10
+        });
+        assert_eq!(
+            results,
+            items.iter().map(|item| item * 10).collect::<Vec<_>>()
+        );
+        assert_ne!(finished.into_inner().unwrap(), items);
+        assert!(workers.into_inner().unwrap().len() > 1);
+    }
+}
@@ -0,0 +1,365 @@
+use coord_frames::Angle;
+use eta3_spline::*;
+
+/// A planar curve through a list of waypoints, with each span between waypoints taking an equal
+/// share of `t` from 0 to 1. Positions are in the same units as the waypoints.
+pub trait Curve {
+    fn eval(&self, t: f64) -> (f64, f64);
+}
+
+impl Curve for EtaCurve<f64> {
+    fn eval(&self, t: f64) -> (f64, f64) {
+        EtaCurve::eval(self, t)
+    }
+}
+
+/// The family of curve a segment is built from.
+#[derive(Debug, Clone)]
+pub enum Spline {
+    /// eta^3 splines, shaped by six parameters per span.
+    Eta3(Vec<EtaParam<f64>>),
+    /// Matches position, heading and curvature at every waypoint.
+    QuinticHermite,
+    /// Matches position and heading at every waypoint, with control points a third of the way
+    /// along each span.
+    CubicBezier,
+    /// One Euler spiral per span, whose curvature changes linearly with distance. Curvature is
+    /// continuous within a span, but waypoint curvature is not matched.
+    Clothoid,
+}
+
+impl From<Vec<EtaParam<f64>>> for Spline {
+    fn from(params: Vec<EtaParam<f64>>) -> Self {
+        Spline::Eta3(params)
+    }
+}
+
+impl Spline {
+    /// The families that need no parameters, so can be picked from the command line.
+    pub const NAMES: &'static [&'static str] = &["hermite", "bezier", "clothoid"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "hermite" => Spline::QuinticHermite,
+            "bezier" => Spline::CubicBezier,
+            "clothoid" => Spline::Clothoid,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Spline::Eta3(_) => "eta3",
+            Spline::QuinticHermite => "hermite",
+            Spline::CubicBezier => "bezier",
+            Spline::Clothoid => "clothoid",
+        }
+    }
+
+    /// Builds the curve through `states`, or `None` if this family can't pass through them.
+    pub fn curve(&self, states: &[MotionState<f64>]) -> Option<Box<dyn Curve>> {
+        if states.len() < 2 {
+            return None;
+        }
+        Some(match self {
+            Spline::Eta3(params) => Box::new(EtaCurve::new(states, params)?),
+            Spline::QuinticHermite => Box::new(Piecewise(
+                states
+                    .windows(2)
+                    .map(|s| Hermite::new(s[0], s[1]))
+                    .collect(),
+            )),
+            Spline::CubicBezier => Box::new(Piecewise(
+                states.windows(2).map(|s| Bezier::new(s[0], s[1])).collect(),
+            )),
+            Spline::Clothoid => Box::new(Piecewise(
+                states
+                    .windows(2)
+                    .map(|s| Clothoid::fit(s[0], s[1]))
+                    .collect::<Option<Vec<_>>>()?,
+            )),
+        })
+    }
+}
+
+/// One span of a curve, with its own `t` from 0 to 1.
+trait Span {
+    fn eval(&self, t: f64) -> (f64, f64);
+}
+
+struct Piecewise<S>(Vec<S>);
+
+impl<S: Span> Curve for Piecewise<S> {
+    fn eval(&self, t: f64) -> (f64, f64) {
+        let n = self.0.len();
+        let s = (t * n as f64).max(0.0).min(n as f64);
+        let i = (s.floor() as usize).min(n - 1);
+        self.0[i].eval(s - i as f64)
+    }
+}
+
+fn chord(a: MotionState<f64>, b: MotionState<f64>) -> f64 {
+    f64::sqrt((b.x - a.x).powi(2) + (b.y - a.y).powi(2))
+}
+
+struct Hermite {
+    /// Position, first and second derivative at each end.
+    p: [(f64, f64); 2],
+    v: [(f64, f64); 2],
+    a: [(f64, f64); 2],
+}
+
+impl Hermite {
+    fn new(start: MotionState<f64>, end: MotionState<f64>) -> Self {
+        // moving at the chord length per unit t, curvature is all sideways acceleration
+        let speed = chord(start, end);
+        let v = |s: MotionState<f64>| (speed * s.t.cos(), speed * s.t.sin());
+        let a = |s: MotionState<f64>| {
+            let n = s.k * speed * speed;
+            (-n * s.t.sin(), n * s.t.cos())
+        };
+        Self {
+            p: [(start.x, start.y), (end.x, end.y)],
+            v: [v(start), v(end)],
+            a: [a(start), a(end)],
+        }
+    }
+}
+
+impl Span for Hermite {
+    fn eval(&self, t: f64) -> (f64, f64) {
+        let (t2, t3) = (t * t, t * t * t);
+        let (t4, t5) = (t3 * t, t3 * t2);
+        let h = [
+            1. - 10. * t3 + 15. * t4 - 6. * t5,
+            t - 6. * t3 + 8. * t4 - 3. * t5,
+            0.5 * t2 - 1.5 * t3 + 1.5 * t4 - 0.5 * t5,
+            0.5 * t3 - t4 + 0.5 * t5,
+            -4. * t3 + 7. * t4 - 3. * t5,
+            10. * t3 - 15. * t4 + 6. * t5,
+        ];
+        let terms = [
+            self.p[0], self.v[0], self.a[0], self.a[1], self.v[1], self.p[1],
+        ];
+        terms
+            .iter()
+            .zip(&h)
+            .fold((0.0, 0.0), |sum, (c, h)| (sum.0 + c.0 * h, sum.1 + c.1 * h))
+    }
+}
+
+struct Bezier([(f64, f64); 4]);
+
+impl Bezier {
+    fn new(start: MotionState<f64>, end: MotionState<f64>) -> Self {
+        let d = chord(start, end) / 3.;
+        Bezier([
+            (start.x, start.y),
+            (start.x + d * start.t.cos(), start.y + d * start.t.sin()),
+            (end.x - d * end.t.cos(), end.y - d * end.t.sin()),
+            (end.x, end.y),
+        ])
+    }
+}
+
+impl Span for Bezier {
+    fn eval(&self, t: f64) -> (f64, f64) {
+        let u = 1. - t;
+        let w = [u * u * u, 3. * u * u * t, 3. * u * t * t, t * t * t];
+        self.0
+            .iter()
+            .zip(&w)
+            .fold((0.0, 0.0), |sum, (c, w)| (sum.0 + c.0 * w, sum.1 + c.1 * w))
+    }
+}
+
+/// Intervals used to integrate the Fresnel-like integrals of a clothoid.
+const CLOTHOID_STEPS: usize = 64;
+
+/// `heading(s) = start heading + k0 * s + dk * s^2 / 2` over `0 <= s <= length`.
+struct Clothoid {
+    start: (f64, f64),
+    heading: f64,
+    k0: f64,
+    dk: f64,
+    length: f64,
+}
+
+/// `∫₀¹ (cos, sin)(a/2 τ² + b τ + c) τ^m dτ`, by Simpson's rule.
+fn fresnel(a: f64, b: f64, c: f64, m: i32) -> (f64, f64) {
+    let n = CLOTHOID_STEPS;
+    let h = 1. / n as f64;
+    let mut sum = (0.0, 0.0);
+    for i in 0..=n {
+        let tau = i as f64 * h;
+        let w = if i == 0 || i == n {
+            1.
+        } else if i % 2 == 1 {
+            4.
+        } else {
+            2.
+        };
+        let angle = a / 2. * tau * tau + b * tau + c;
+        let scale = w * tau.powi(m);
+        sum.0 += scale * angle.cos();
+        sum.1 += scale * angle.sin();
+    }
+    (sum.0 * h / 3., sum.1 * h / 3.)
+}
+
+impl Clothoid {
+    /// Fits the clothoid from `start` to `end` matching both positions and headings, as in
+    /// Bertolazzi and Frego's "G1 fitting with clothoids", or `None` if the fit fails.
+    fn fit(start: MotionState<f64>, end: MotionState<f64>) -> Option<Self> {
+        let (dx, dy) = (end.x - start.x, end.y - start.y);
+        let r = f64::sqrt(dx * dx + dy * dy);
+        if r < 1e-9 {
+            return None;
+        }
+        let phi = Angle::from_radians(dy.atan2(dx));
+        let phi0 = (Angle::from_radians(start.t) - phi).radians();
+        let phi1 = (Angle::from_radians(end.t) - phi).radians();
+        let delta = phi1 - phi0;
+
+        // find the A at which the spiral ends on the chord, starting from their approximation
+        let mut a = 3. * (phi0 + phi1);
+        for _ in 0..50 {
+            let g = fresnel(2. * a, delta - a, phi0, 0).1;
+            let (g1, g2) = (
+                fresnel(2. * a, delta - a, phi0, 1).0,
+                fresnel(2. * a, delta - a, phi0, 2).0,
+            );
+            let dg = g2 - g1;
+            if dg.abs() < 1e-12 {
+                break;
+            }
+            let step = g / dg;
+            a -= step;
+            if step.abs() < 1e-12 {
+                break;
+            }
+        }
+        let (x, y) = fresnel(2. * a, delta - a, phi0, 0);
+        // written so that a fit gone to NaN fails too
+        if !(y.abs() <= 1e-6 && x > 0.0) {
+            return None;
+        }
+
+        let length = r / x;
+        Some(Clothoid {
+            start: (start.x, start.y),
+            heading: start.t,
+            k0: (delta - a) / length,
+            dk: 2. * a / (length * length),
+            length,
+        })
+    }
+}
+
+impl Span for Clothoid {
+    fn eval(&self, t: f64) -> (f64, f64) {
+        let s = t * self.length;
+        let (x, y) = fresnel(self.dk * s * s, self.k0 * s, self.heading, 0);
+        (self.start.0 + s * x, self.start.1 + s * y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn state(x: f64, y: f64, t: f64, k: f64) -> MotionState<f64> {
+        MotionState { x, y, t, k, dk: 0. }
+    }
+
+    const WAYPOINTS: &[(f64, f64, f64)] = &[(0., 0., 0.), (4., 2., PI / 4.), (6., 6., PI / 2.)];
+
+    fn heading(curve: &dyn Curve, t: f64) -> f64 {
+        let h = 1e-6;
+        let (a, b) = (curve.eval(t - h), curve.eval(t + h));
+        (b.1 - a.1).atan2(b.0 - a.0)
+    }
+
+    #[test]
+    fn every_spline_passes_through_its_waypoints() {
+        let states: Vec<_> = WAYPOINTS
+            .iter()
+            .map(|&(x, y, t)| state(x, y, t, 0.))
+            .collect();
+        for spline in &[
+            Spline::QuinticHermite,
+            Spline::CubicBezier,
+            Spline::Clothoid,
+        ] {
+            let curve = spline.curve(&states).unwrap();
+            let spans = (states.len() - 1) as f64;
+            for (i, s) in states.iter().enumerate() {
+                let t = i as f64 / spans;
+                let (x, y) = curve.eval(t);
+                assert!((x - s.x).abs() < 1e-6, "{} x at {}", spline.name(), i);
+                assert!((y - s.y).abs() < 1e-6, "{} y at {}", spline.name(), i);
+                // just inside the curve at the ends
+                let t = t.clamp(1e-5, 1. - 1e-5);
+                let turn = Angle::from_radians(s.t)
+                    .shortest_diff(Angle::from_radians(heading(&*curve, t)));
+                assert!(
+                    turn.radians().abs() < 1e-3,
+                    "{} heading at {}",
+                    spline.name(),
+                    i
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hermite_matches_waypoint_curvature() {
+        let states = [state(0., 0., 0., 0.2), state(5., 3., 0.8, -0.1)];
+        let curve = Spline::QuinticHermite.curve(&states).unwrap();
+        let curvature = |t: f64| {
+            let h = 1e-4;
+            let ds = {
+                let (a, b) = (curve.eval(t), curve.eval(t + h));
+                f64::hypot(b.0 - a.0, b.1 - a.1)
+            };
+            Angle::from_radians(heading(&*curve, t))
+                .shortest_diff(Angle::from_radians(heading(&*curve, t + h)))
+                .radians()
+                / ds
+        };
+        assert!((curvature(1e-4) - 0.2).abs() < 1e-2);
+        assert!((curvature(1. - 2e-4) + 0.1).abs() < 1e-2);
+    }
+
+    #[test]
+    fn clothoid_curvature_is_linear_in_distance() {
+        let clothoid = Clothoid::fit(state(0., 0., 0., 0.), state(3., 1., 1.2, 0.)).unwrap();
+        assert!(clothoid.dk.abs() > 0.1);
+        let at = |s: f64| clothoid.eval(s / clothoid.length);
+        let direction = |s: f64| {
+            let (a, b) = (at(s - 1e-4), at(s + 1e-4));
+            (b.1 - a.1).atan2(b.0 - a.0)
+        };
+        for i in 1..10 {
+            let s = clothoid.length * i as f64 / 10.;
+            let h = 1e-3;
+            let numeric = (direction(s + h) - direction(s - h)) / (2. * h);
+            let expected = clothoid.k0 + clothoid.dk * s;
+            assert!((numeric - expected).abs() < 1e-3, "at {} ft", s);
+        }
+        let end = clothoid.heading
+            + clothoid.k0 * clothoid.length
+            + clothoid.dk * clothoid.length.powi(2) / 2.;
+        assert!((end - 1.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn clothoid_fit_fails_without_converging() {
+        // Newton's method on a NaN heading never settles on the chord
+        assert!(Clothoid::fit(state(0., 0., f64::NAN, 0.), state(1., 0., 0., 0.)).is_none());
+        assert!(Clothoid::fit(state(1., 1., 0., 0.), state(1., 1., 0.5, 0.)).is_none());
+        let states = [state(0., 0., 0., 0.), state(2., 0., f64::NAN, 0.)];
+        assert!(Spline::Clothoid.curve(&states).is_none());
+    }
+}
@@ -0,0 +1,367 @@
+//! Paths defined in JSON files instead of in `autos`, so they can be edited without a rebuild.
+//!
+//! Each file in the definitions directory holds `{"paths": [...]}`, where a path is
+//!
+//! ```json
+//! {
+//!   "name": "rightToPlatform",
+//!   "mirror": "leftToPlatform",
+//!   "segments": [
+//!     {
+//!       "waypoints": [
+//!         {"frame": "sideStart"},
+//!         {"frame": "switchLane", "x": 1.5, "heading": 90, "curvature": 0.1}
+//!       ],
+//!       "eta": [[5, 5, 0, 0, 0, 0]],
+//!       "points": 300
+//!     },
+//!     {
+//!       "reversed": true,
+//!       "spline": "hermite",
+//!       "waypoints": [...]
+//!     }
+//!   ],
+//!   "markers": [{"name": "raiseElevator", "distance": 3}]
+//! }
+//! ```
+//!
+//! Everything is in meters and degrees: waypoints are poses in one of the field's frames with
+//! curvature in 1/m, and marker distances are in meters. Giving a `side` (`front`, `back`, `left`
+//! or `right`) and optionally a `standoff` in meters treats the pose as a target for that side of
+//! the robot to approach, and `turn` rotates the robot in place after that. `mirror` on a waypoint
+//! moves it to the left side of the field, while `mirror` on a path also generates its mirror image
+//! under that name. Markers are placed at a `distance`, `distanceFromEnd`, `fraction` or waypoint
+//! `segment` and `index`.
+
+use crate::approach::Side;
+use crate::autos;
+use crate::curve::Spline;
+use crate::frames::PathFrame;
+use crate::path::{MarkerPosition, PathChain, Segment};
+use crate::robot::Robot;
+use crate::FEET_PER_METER;
+use coord_frames::*;
+use dim::si;
+use eta3_spline::*;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where definition files are looked for, relative to the generator's working directory.
+pub const DEFAULT_DIR: &str = "paths";
+
+/// One definition file. Everything left at its default is left out when it is saved again.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DefFile {
+    pub paths: Vec<PathDef>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PathDef {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<String>,
+    pub segments: Vec<SegmentDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<MarkerDef>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SegmentDef {
+    pub waypoints: Vec<Waypoint>,
+    /// Six parameters per span between waypoints.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub eta: Vec<[f64; 6]>,
+    /// One of `Spline::NAMES`, instead of `eta`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spline: Option<String>,
+    #[serde(default = "default_points")]
+    pub points: usize,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub reversed: bool,
+}
+
+fn default_points() -> usize {
+    300
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Waypoint {
+    #[serde(default = "field_frame", skip_serializing_if = "is_field")]
+    pub frame: PathFrame,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub x: f64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub y: f64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub heading: f64,
+    /// In 1/m, positive turning left.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub curvature: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub standoff: f64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub turn: f64,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub mirror: bool,
+}
+
+fn field_frame() -> PathFrame {
+    PathFrame::Field
+}
+
+fn is_field(frame: &PathFrame) -> bool {
+    *frame == PathFrame::Field
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MarkerDef {
+    pub name: String,
+    #[serde(flatten)]
+    pub at: MarkerAt,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MarkerAt {
+    Waypoint {
+        segment: usize,
+        index: usize,
+    },
+    DistanceFromEnd {
+        #[serde(rename = "distanceFromEnd")]
+        distance_from_end: f64,
+    },
+    Distance {
+        distance: f64,
+    },
+    Fraction {
+        fraction: f64,
+    },
+}
+
+#[derive(Debug)]
+pub enum DefError {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    /// A path with no segments.
+    Empty(PathBuf, String),
+    /// A segment with neither eta parameters nor a known spline.
+    Spline {
+        file: PathBuf,
+        path: String,
+        segment: usize,
+    },
+    /// A path name used by more than one path.
+    Duplicate(String),
+}
+
+impl fmt::Display for DefError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DefError::Io(file, e) => write!(f, "{}: {}", file.display(), e),
+            DefError::Json(file, e) => write!(f, "{}: {}", file.display(), e),
+            DefError::Empty(file, path) => {
+                write!(f, "{}: path {} has no segments", file.display(), path)
+            }
+            DefError::Spline {
+                file,
+                path,
+                segment,
+            } => write!(
+                f,
+                "{}: segment {} of path {} needs eta parameters or a spline from {}",
+                file.display(),
+                segment,
+                path,
+                Spline::NAMES.join(", ")
+            ),
+            DefError::Duplicate(name) => write!(f, "path {} is defined twice", name),
+        }
+    }
+}
+
+impl Error for DefError {}
+
+/// The definition files in `dir`, sorted so paths are always generated in the same order. A
+/// missing directory just has no definitions in it.
+pub fn files(dir: &Path) -> Result<Vec<PathBuf>, DefError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(DefError::Io(dir.to_owned(), e)),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let file = entry.map_err(|e| DefError::Io(dir.to_owned(), e))?.path();
+        if file.extension().is_some_and(|ext| ext == "json") {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+impl DefFile {
+    pub fn read(file: &Path) -> Result<Self, DefError> {
+        let bytes = fs::read(file).map_err(|e| DefError::Io(file.to_owned(), e))?;
+        serde_json::from_slice(&bytes).map_err(|e| DefError::Json(file.to_owned(), e))
+    }
+
+    /// Writes the definitions back to `file`, one value per line so edits diff cleanly.
+    pub fn save(&self, file: &Path) -> Result<(), DefError> {
+        let mut json =
+            serde_json::to_vec_pretty(self).map_err(|e| DefError::Json(file.to_owned(), e))?;
+        json.push(b'\n');
+        fs::write(file, json).map_err(|e| DefError::Io(file.to_owned(), e))
+    }
+}
+
+/// Builds the paths defined in `file`, placing waypoints with `frames` and `robot`'s footprint.
+pub fn load(
+    file: &Path,
+    frames: &FrameRegistry<PathFrame>,
+    robot: &Robot,
+) -> Result<Vec<(String, PathChain)>, DefError> {
+    let defs = DefFile::read(file)?;
+    let mut paths = Vec::new();
+    for def in &defs.paths {
+        paths.push((def.name.clone(), def.build(file, frames, robot, false)?));
+        if let Some(ref mirror) = def.mirror {
+            paths.push((mirror.clone(), def.build(file, frames, robot, true)?));
+        }
+    }
+    Ok(paths)
+}
+
+impl PathDef {
+    /// Builds the path, or its mirror image. `file` is only for naming it in errors.
+    pub fn build(
+        &self,
+        file: &Path,
+        frames: &FrameRegistry<PathFrame>,
+        robot: &Robot,
+        mirror: bool,
+    ) -> Result<PathChain, DefError> {
+        let mut segments = Vec::new();
+        for (i, def) in self.segments.iter().enumerate() {
+            let spline = match def.spline {
+                Some(ref name) => Spline::from_name(name),
+                None if !def.eta.is_empty() => Some(Spline::Eta3(
+                    def.eta
+                        .iter()
+                        .map(|e| EtaParam::new(e[0], e[1], e[2], e[3], e[4], e[5]))
+                        .collect(),
+                )),
+                None => None,
+            };
+            let spline = spline.ok_or_else(|| DefError::Spline {
+                file: file.to_owned(),
+                path: self.name.clone(),
+                segment: i,
+            })?;
+            let points = def
+                .waypoints
+                .iter()
+                .map(|w| w.state(frames, robot, mirror))
+                .collect();
+            segments.push(if def.reversed {
+                Segment::reverse(points, spline, def.points)
+            } else {
+                Segment::forward(points, spline, def.points)
+            });
+        }
+
+        let mut segments = segments.into_iter();
+        let first = segments
+            .next()
+            .ok_or_else(|| DefError::Empty(file.to_owned(), self.name.clone()))?;
+        let mut chain = segments.fold(PathChain::new(first), PathChain::then);
+        for marker in &self.markers {
+            let position = match marker.at {
+                MarkerAt::Waypoint { segment, index } => {
+                    MarkerPosition::Waypoint { segment, index }
+                }
+                MarkerAt::DistanceFromEnd { distance_from_end } => {
+                    MarkerPosition::DistanceFromEnd(distance_from_end * si::M)
+                }
+                MarkerAt::Distance { distance } => MarkerPosition::Distance(distance * si::M),
+                MarkerAt::Fraction { fraction } => MarkerPosition::Fraction(fraction),
+            };
+            chain = chain.with_marker(&marker.name, position);
+        }
+        Ok(chain)
+    }
+}
+
+impl Waypoint {
+    /// The robot's state at this waypoint, in feet on the field.
+    pub fn state(
+        &self,
+        frames: &FrameRegistry<PathFrame>,
+        robot: &Robot,
+        mirror: bool,
+    ) -> MotionState<f64> {
+        let target = TfPoint::new(
+            self.frame,
+            self.x * si::M,
+            self.y * si::M,
+            Angle::from_degrees(self.heading),
+        );
+        let pose = match self.side {
+            Some(side) => robot
+                .footprint()
+                .approach(target, side, self.standoff * si::M),
+            None => target,
+        }
+        .compose(PointData::xyr(0., 0., Angle::from_degrees(self.turn)))
+        .in_frame(frames, PathFrame::Field)
+        .raw_data();
+        // paths are sampled in feet
+        let curvature = self.curvature / FEET_PER_METER;
+        // mirroring turns the other way, so flips the curvature too
+        if self.mirror != mirror {
+            autos::with_kappa(pose.mirror(Axis::Y), -curvature, 0.)
+        } else {
+            autos::with_kappa(pose, curvature, 0.)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn waypoints_are_in_meters() {
+        let field = Field::load(Path::new("fields/2018.json")).unwrap();
+        let robot = Robot::load(Path::new("robots/2018.json")).unwrap();
+        let frames = autos::frames(&field, &robot).unwrap();
+        let waypoint: Waypoint =
+            serde_json::from_str(r#"{"x": 1, "y": 2, "heading": 90, "curvature": 0.5}"#).unwrap();
+
+        let state = waypoint.state(&frames, &robot, false);
+        assert!((state.x - FEET_PER_METER).abs() < 1e-9);
+        assert!((state.y - 2. * FEET_PER_METER).abs() < 1e-9);
+        // 0.5 1/m is about 0.15 1/ft
+        assert!((state.k - 0.5 / FEET_PER_METER).abs() < 1e-9);
+
+        let mirrored = waypoint.state(&frames, &robot, true);
+        assert!((mirrored.x + FEET_PER_METER).abs() < 1e-9);
+        assert!((mirrored.k + 0.5 / FEET_PER_METER).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,425 @@
+//! Compares the outputs of two generator runs, to see which autos a change to a constant or the
+//! field moved, and by how much.
+
+use crate::export::RobotPose;
+use crate::geometry::dist;
+use crate::parse::{self, ParseError};
+use crate::path::SampledPath;
+use coord_frames::Angle;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+/// Differences smaller than this, in feet or radians, are rounding and don't count.
+pub const TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug)]
+pub enum DiffError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, ParseError),
+    /// A command needed to generate another revision's outputs failed.
+    Command {
+        command: String,
+        output: String,
+    },
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            DiffError::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+            DiffError::Command { command, output } => {
+                write!(f, "{} failed", command)?;
+                if !output.is_empty() {
+                    write!(f, ":\n{}", output)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for DiffError {}
+
+/// The paths and poses found in an output directory, by name.
+struct Outputs {
+    paths: BTreeMap<String, SampledPath>,
+    poses: BTreeMap<String, RobotPose>,
+}
+
+impl Outputs {
+    /// Reads every `.114path` file and every pose, whichever format it was exported in, from
+    /// `dir`. Java files that aren't poses are path arrays or the class, and are skipped.
+    fn read(dir: &Path) -> Result<Self, DiffError> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).map_err(|e| DiffError::Io(dir.to_owned(), e))? {
+            files.push(entry.map_err(|e| DiffError::Io(dir.to_owned(), e))?.path());
+        }
+        files.sort();
+
+        let mut outputs = Outputs {
+            paths: BTreeMap::new(),
+            poses: BTreeMap::new(),
+        };
+        for file in files {
+            let name = match file.file_name().and_then(|n| n.to_str()) {
+                Some(name) => name.to_owned(),
+                None => continue,
+            };
+            if let Some(path) = name.strip_suffix(".114path") {
+                let read = parse::read_114path(&file).map_err(|e| DiffError::Parse(file, e))?;
+                outputs.paths.insert(path.to_owned(), read);
+                continue;
+            }
+            let pose = [".pose.json", ".114pose", ".pose.java"]
+                .iter()
+                .find_map(|ext| name.strip_suffix(ext));
+            if let Some(pose) = pose {
+                match parse::read_pose(&file) {
+                    // a Java declaration may be left over from an earlier run in the default
+                    // formats, so any other format wins
+                    Ok(read) if name.ends_with(".java") => {
+                        outputs.poses.entry(pose.to_owned()).or_insert(read);
+                    }
+                    Ok(read) => {
+                        outputs.poses.insert(pose.to_owned(), read);
+                    }
+                    Err(e) => return Err(DiffError::Parse(file, e)),
+                }
+            }
+        }
+        Ok(outputs)
+    }
+}
+
+/// The driven points of `path`, without the end point interpolation.
+fn driven(path: &SampledPath) -> Vec<(f64, f64)> {
+    path.points
+        .iter()
+        .filter(|p| !p.interpolated)
+        .map(|p| (p.x, p.y))
+        .collect()
+}
+
+/// How far `p` is from the nearest point of the segment from `a` to `b`.
+fn to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let squared = dx * dx + dy * dy;
+    if squared < 1e-12 {
+        return dist(p, a);
+    }
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / squared).clamp(0.0, 1.0);
+    dist(p, (a.0 + t * dx, a.1 + t * dy))
+}
+
+/// The furthest any point of `from` is from the polyline `to`.
+fn furthest(from: &[(f64, f64)], to: &[(f64, f64)]) -> f64 {
+    from.iter()
+        .map(|&p| match to {
+            [only] => dist(p, *only),
+            _ => to
+                .windows(2)
+                .map(|w| to_segment(p, w[0], w[1]))
+                .fold(f64::INFINITY, f64::min),
+        })
+        .fold(0.0, f64::max)
+}
+
+fn end_heading(path: &SampledPath) -> Angle {
+    Angle::from_radians(path.end_direction.1.atan2(path.end_direction.0))
+}
+
+/// Describes how a path moved, or `None` if it didn't.
+fn diff_path(old: &SampledPath, new: &SampledPath) -> Option<String> {
+    let (a, b) = (driven(old), driven(new));
+    if a.is_empty() || b.is_empty() {
+        return Some("has no driven points".to_owned());
+    }
+    // how far apart the two are anywhere, however differently they're sampled
+    let deviation = furthest(&a, &b).max(furthest(&b, &a));
+    let length = |path: &SampledPath| {
+        path.points
+            .iter()
+            .rfind(|p| !p.interpolated)
+            .map_or(0.0, |p| p.distance)
+    };
+    let longer = length(new) - length(old);
+    let end_moved = dist(a[a.len() - 1], b[b.len() - 1]);
+    let end_turned = end_heading(old).shortest_diff(end_heading(new));
+
+    if deviation < TOLERANCE
+        && longer.abs() < TOLERANCE
+        && end_moved < TOLERANCE
+        && end_turned.radians().abs() < TOLERANCE
+    {
+        return None;
+    }
+    Some(format!(
+        "max deviation {:.3} ft, length {:+.3} ft, end moved {:.3} ft, end heading {:+.2} deg",
+        deviation,
+        longer,
+        end_moved,
+        end_turned.degrees()
+    ))
+}
+
+fn diff_pose(old: RobotPose, new: RobotPose) -> Option<String> {
+    let moved = dist((old.x, old.y), (new.x, new.y));
+    let turned = Angle::from_radians(old.heading).shortest_diff(Angle::from_radians(new.heading));
+    if moved < TOLERANCE && turned.radians().abs() < TOLERANCE {
+        return None;
+    }
+    Some(format!(
+        "moved {:.3} ft, heading {:+.2} deg",
+        moved,
+        turned.degrees()
+    ))
+}
+
+/// How an output differs between two runs.
+#[derive(Debug, PartialEq)]
+enum Change {
+    Added,
+    Removed,
+    /// In both, but different, as described.
+    Changed(String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added => write!(f, "added"),
+            Change::Removed => write!(f, "removed"),
+            Change::Changed(change) => write!(f, "{}", change),
+        }
+    }
+}
+
+/// Every name in `old` or `new` whose entry changed, appeared or disappeared, in order, and how
+/// many are unchanged.
+fn compare<'a, T>(
+    old: &'a BTreeMap<String, T>,
+    new: &'a BTreeMap<String, T>,
+    diff: impl Fn(&T, &T) -> Option<String>,
+) -> (Vec<(&'a str, Change)>, usize) {
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    let names = old
+        .keys()
+        .chain(new.keys().filter(|name| !old.contains_key(*name)));
+    let mut names: Vec<&String> = names.collect();
+    names.sort();
+    for name in names {
+        let change = match (old.get(name), new.get(name)) {
+            (Some(a), Some(b)) => match diff(a, b) {
+                Some(change) => Change::Changed(change),
+                None => {
+                    unchanged += 1;
+                    continue;
+                }
+            },
+            (None, Some(_)) => Change::Added,
+            (Some(_), None) => Change::Removed,
+            (None, None) => unreachable!(),
+        };
+        changes.push((name.as_str(), change));
+    }
+    (changes, unchanged)
+}
+
+/// Prints every path and pose that differs between the outputs in `old` and `new`.
+pub fn report(old: &Path, new: &Path) -> Result<(), DiffError> {
+    let (old, new) = (Outputs::read(old)?, Outputs::read(new)?);
+    let (paths, paths_unchanged) = compare(&old.paths, &new.paths, diff_path);
+    let (poses, poses_unchanged) = compare(&old.poses, &new.poses, |a, b| diff_pose(*a, *b));
+    for (name, change) in &paths {
+        println!("path {}: {}", name, change);
+    }
+    for (name, change) in &poses {
+        println!("pose {}: {}", name, change);
+    }
+    println!("{} unchanged", paths_unchanged + poses_unchanged);
+    Ok(())
+}
+
+/// Runs a command, failing with whatever it printed if it fails.
+fn run(command: &mut Command, describe: &str) -> Result<String, DiffError> {
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| DiffError::Io(PathBuf::from(describe), e))?;
+    if !output.status.success() {
+        return Err(DiffError::Command {
+            command: describe.to_owned(),
+            output: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+fn git(args: &[&str]) -> Result<String, DiffError> {
+    run(
+        Command::new("git").args(args),
+        &format!("git {}", args.join(" ")),
+    )
+}
+
+/// A scratch checkout of another revision, removed again when dropped.
+pub struct Checkout {
+    root: PathBuf,
+    /// Where that revision's generator wrote its outputs.
+    pub out_dir: PathBuf,
+}
+
+impl Checkout {
+    /// Checks out `rev` and runs its generator with its default settings, from the same place
+    /// in the tree this one is run from. Builds go in their own target directory so they don't
+    /// replace this build.
+    pub fn generate(rev: &str) -> Result<Self, DiffError> {
+        let top = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?);
+        let prefix = git(&["rev-parse", "--show-prefix"])?;
+        let root = std::env::temp_dir().join(format!("paths-2018-diff-{}", process::id()));
+        let root_arg = root.to_string_lossy().into_owned();
+        git(&["worktree", "add", "--detach", &root_arg, rev])?;
+        let checkout = Checkout {
+            out_dir: root.join(&prefix).join("out"),
+            root,
+        };
+
+        let dir = checkout.root.join(&prefix);
+        let target = top.join("target").join("diff-rev");
+        println!("building {}", rev);
+        run(
+            Command::new("cargo")
+                .args(["build", "--quiet"])
+                .current_dir(&dir)
+                .env("CARGO_TARGET_DIR", &target),
+            &format!("cargo build of {}", rev),
+        )?;
+        fs::create_dir_all(&checkout.out_dir)
+            .map_err(|e| DiffError::Io(checkout.out_dir.clone(), e))?;
+        // a run with some failed outputs still wrote the rest, which are worth comparing
+        let generated = run(
+            Command::new("cargo")
+                .args(["run", "--quiet"])
+                .current_dir(&dir)
+                .env("CARGO_TARGET_DIR", &target),
+            &format!("generator at {}", rev),
+        );
+        if let Err(e) = generated {
+            eprintln!("{}", e);
+        }
+        Ok(checkout)
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        let root = self.root.to_string_lossy().into_owned();
+        if let Err(e) = git(&["worktree", "remove", "--force", &root]) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PathPoint;
+
+    /// A path through `points`, in feet, sampled as given.
+    fn path(points: &[(f64, f64)]) -> SampledPath {
+        let mut distance = 0.0;
+        SampledPath {
+            points: points
+                .iter()
+                .enumerate()
+                .map(|(i, &(x, y))| {
+                    if i > 0 {
+                        distance += dist(points[i - 1], (x, y));
+                    }
+                    PathPoint {
+                        x,
+                        y,
+                        distance,
+                        interpolated: false,
+                        reversed: false,
+                    }
+                })
+                .collect(),
+            end_direction: (1., 0.),
+            markers: Vec::new(),
+        }
+    }
+
+    /// `n` evenly spaced points from `a` to `b`.
+    fn line(a: (f64, f64), b: (f64, f64), n: usize) -> Vec<(f64, f64)> {
+        (0..n)
+            .map(|i| {
+                let t = i as f64 / (n - 1) as f64;
+                (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn furthest_measures_to_the_polyline() {
+        let to = line((0., 0.), (10., 0.), 2);
+        // between the vertices, not just to them
+        assert!((furthest(&line((0., 1.), (10., 1.), 7), &to) - 1.).abs() < 1e-12);
+        // past the end, to the end
+        assert!((furthest(&[(13., 4.)], &to) - 5.).abs() < 1e-12);
+        assert!((furthest(&[(3., 4.)], &[(0., 0.)]) - 5.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn resampling_is_not_a_change() {
+        let old = path(&line((0., 0.), (10., 0.), 11));
+        let new = path(&line((0., 0.), (10., 0.), 37));
+        assert_eq!(diff_path(&old, &new), None);
+    }
+
+    #[test]
+    fn deviation_is_the_hausdorff_distance() {
+        let old = path(&line((0., 0.), (10., 0.), 11));
+        // bulges half a foot to one side in the middle, and ends in the same place
+        let new = path(&[(0., 0.), (5., 0.5), (10., 0.)]);
+        let change = diff_path(&old, &new).unwrap();
+        assert!(change.starts_with("max deviation 0.500 ft"), "{}", change);
+        assert!(change.contains("end moved 0.000 ft"), "{}", change);
+
+        // a path that stops short is as far off as the part it leaves out
+        let short = path(&line((0., 0.), (7., 0.), 8));
+        let change = diff_path(&old, &short).unwrap();
+        assert!(
+            change.starts_with("max deviation 3.000 ft, length -3.000 ft"),
+            "{}",
+            change
+        );
+    }
+
+    #[test]
+    fn classifies_changes() {
+        let map = |entries: &[(&str, i32)]| -> BTreeMap<String, i32> {
+            entries.iter().map(|&(k, v)| (k.to_owned(), v)).collect()
+        };
+        let old = map(&[("a", 1), ("b", 2), ("c", 3)]);
+        let new = map(&[("b", 2), ("c", 4), ("d", 5)]);
+        let diff = |a: &i32, b: &i32| (a != b).then(|| format!("{} -> {}", a, b));
+        let (changes, unchanged) = compare(&old, &new, diff);
+        assert_eq!(
+            changes,
+            [
+                ("a", Change::Removed),
+                ("c", Change::Changed("3 -> 4".to_owned())),
+                ("d", Change::Added),
+            ]
+        );
+        assert_eq!(unchanged, 1);
+    }
+}
@@ -0,0 +1,529 @@
+//! A terminal editor for path definition files. It draws the field and the path being edited,
+//! rebuilds the path as waypoints and eta parameters are nudged from the keyboard, and saves
+//! back to the definition file.
+
+use crate::autos;
+use crate::defs::{DefFile, SegmentDef};
+use crate::field::Field;
+use crate::frames::PathFrame;
+use crate::path::SampledPath;
+use crate::preview;
+use crate::robot::Robot;
+use crate::validate::{self, Report};
+use coord_frames::FrameRegistry;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Lines under the field for the stats, the selection and the keys.
+const PANEL: u16 = 6;
+
+/// The waypoint values that can be nudged, with their units and step sizes.
+const WAYPOINT_VALUES: &[(&str, &str, f64)] = &[
+    ("x", "m", 0.1),
+    ("y", "m", 0.1),
+    ("heading", "deg", 5.0),
+    ("curvature", "1/m", 0.1),
+];
+const ETA_STEP: f64 = 0.5;
+
+const HELP: &str = "tab/shift-tab waypoint  p path  e waypoint/eta  \u{2190}\u{2192} value  \
+                    \u{2191}\u{2193} nudge  f fine  s save  r revert  q quit";
+
+/// Plots the line from `a` to `b`, which is at most a field long.
+fn line(plot: &mut dyn FnMut(f64, f64, char, Color), a: (f64, f64), b: (f64, f64)) {
+    for i in 0..=100 {
+        let f = i as f64 / 100.;
+        plot(
+            a.0 + (b.0 - a.0) * f,
+            a.1 + (b.1 - a.1) * f,
+            '#',
+            Color::DarkGrey,
+        );
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Waypoint,
+    /// The eta parameters of the span starting at the selected waypoint.
+    Eta,
+}
+
+struct Editor {
+    file: PathBuf,
+    defs: DefFile,
+    field: Field,
+    robot: Robot,
+    frames: FrameRegistry<PathFrame>,
+    path: usize,
+    segment: usize,
+    waypoint: usize,
+    mode: Mode,
+    /// Which value of the waypoint or span is selected.
+    value: usize,
+    /// Steps are a tenth of their usual size.
+    fine: bool,
+    saved: bool,
+    /// Set by the first `q` with unsaved changes, so a second one quits.
+    quitting: bool,
+    status: String,
+    sampled: Result<(SampledPath, Report), String>,
+}
+
+impl Editor {
+    fn segment(&self) -> &SegmentDef {
+        &self.defs.paths[self.path].segments[self.segment]
+    }
+
+    fn segment_mut(&mut self) -> &mut SegmentDef {
+        &mut self.defs.paths[self.path].segments[self.segment]
+    }
+
+    /// The span the eta parameters apply to, which for the last waypoint is the one ending there.
+    fn span(&self) -> usize {
+        self.waypoint
+            .min(self.segment().waypoints.len().saturating_sub(2))
+    }
+
+    fn rebuild(&mut self) {
+        let def = &self.defs.paths[self.path];
+        self.sampled = def
+            .build(&self.file, &self.frames, &self.robot, false)
+            .map_err(|e| e.to_string())
+            .and_then(|chain| chain.sample().map_err(|e| e.to_string()))
+            .map(|path| {
+                let report = validate::validate(&path, &self.robot, &self.field);
+                (path, report)
+            });
+    }
+
+    fn select_path(&mut self, path: usize) {
+        self.path = path;
+        self.segment = 0;
+        self.waypoint = 0;
+        self.rebuild();
+    }
+
+    /// Moves the selection `step` waypoints along the path, across segment boundaries.
+    fn select_waypoint(&mut self, step: isize) {
+        let segments = &self.defs.paths[self.path].segments;
+        let all: Vec<(usize, usize)> = segments
+            .iter()
+            .enumerate()
+            .flat_map(|(s, segment)| (0..segment.waypoints.len()).map(move |w| (s, w)))
+            .collect();
+        if all.is_empty() {
+            return;
+        }
+        let current = all
+            .iter()
+            .position(|&at| at == (self.segment, self.waypoint))
+            .unwrap_or(0) as isize;
+        let next = (current + step).rem_euclid(all.len() as isize) as usize;
+        let (segment, waypoint) = all[next];
+        self.segment = segment;
+        self.waypoint = waypoint;
+    }
+
+    fn values(&self) -> usize {
+        match self.mode {
+            Mode::Waypoint => WAYPOINT_VALUES.len(),
+            Mode::Eta => 6,
+        }
+    }
+
+    fn nudge(&mut self, direction: f64) {
+        let scale = if self.fine { 0.1 } else { 1.0 } * direction;
+        // keep the saved numbers short rather than accumulating float error
+        let step = |v: &mut f64, by: f64| *v = ((*v + by * scale) * 1e6).round() / 1e6;
+        let (waypoint, value, span) = (self.waypoint, self.value, self.span());
+        match self.mode {
+            Mode::Waypoint => {
+                let by = WAYPOINT_VALUES[value].2;
+                let w = match self.segment_mut().waypoints.get_mut(waypoint) {
+                    Some(w) => w,
+                    None => return,
+                };
+                match value {
+                    0 => step(&mut w.x, by),
+                    1 => step(&mut w.y, by),
+                    2 => step(&mut w.heading, by),
+                    _ => step(&mut w.curvature, by),
+                }
+            }
+            Mode::Eta => {
+                let segment = self.segment_mut();
+                if let Some(ref spline) = segment.spline {
+                    self.status = format!(
+                        "segment uses the {} spline, which has no parameters",
+                        spline
+                    );
+                    return;
+                }
+                match segment.eta.get_mut(span) {
+                    Some(params) => step(&mut params[value], ETA_STEP),
+                    None => {
+                        self.status = format!("segment has no eta parameters for span {}", span);
+                        return;
+                    }
+                }
+            }
+        }
+        self.saved = false;
+        self.status.clear();
+        self.rebuild();
+    }
+
+    /// Handles a key, returning whether to keep editing.
+    fn key(&mut self, key: KeyEvent) -> bool {
+        let quitting = self.quitting;
+        self.quitting = false;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if self.saved || quitting {
+                    return false;
+                }
+                self.quitting = true;
+                self.status = "unsaved changes, q again to quit without saving".to_owned();
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Tab => self.select_waypoint(1),
+            KeyCode::BackTab => self.select_waypoint(-1),
+            KeyCode::Char('p') => {
+                let next = (self.path + 1) % self.defs.paths.len();
+                self.select_path(next);
+            }
+            KeyCode::Char('e') => {
+                self.mode = match self.mode {
+                    Mode::Waypoint => Mode::Eta,
+                    Mode::Eta => Mode::Waypoint,
+                };
+                self.value = 0;
+            }
+            KeyCode::Left => self.value = (self.value + self.values() - 1) % self.values(),
+            KeyCode::Right => self.value = (self.value + 1) % self.values(),
+            KeyCode::Up => self.nudge(1.0),
+            KeyCode::Down => self.nudge(-1.0),
+            KeyCode::Char('f') => self.fine = !self.fine,
+            KeyCode::Char('s') => {
+                self.status = match self.defs.save(&self.file) {
+                    Ok(()) => {
+                        self.saved = true;
+                        format!("saved {}", self.file.display())
+                    }
+                    Err(e) => e.to_string(),
+                };
+            }
+            KeyCode::Char('r') => match DefFile::read(&self.file) {
+                Ok(defs) if editable(&defs) => {
+                    self.defs = defs;
+                    self.saved = true;
+                    self.status = format!("reverted to {}", self.file.display());
+                    self.select_path(self.path.min(self.defs.paths.len() - 1));
+                }
+                Ok(_) => self.status = "file no longer has paths to edit".to_owned(),
+                Err(e) => self.status = e.to_string(),
+            },
+            _ => {}
+        }
+        true
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let (cols, map_rows) = (cols as usize, rows.saturating_sub(PANEL).max(1) as usize);
+        let mut grid = vec![vec![(' ', Color::Reset); cols]; map_rows];
+
+        let points = match self.sampled {
+            Ok((ref path, _)) => &path.points[..],
+            Err(_) => &[],
+        };
+        let (left, top, width, height) = preview::extent(&self.field, points);
+        // downfield to the right and the right side of the field down, as in the previews
+        let mut plot = |x: f64, y: f64, c: char, color: Color| {
+            let col = ((y - left) / width * cols as f64).floor();
+            let row = ((x - top) / height * map_rows as f64).floor();
+            if col >= 0.0 && row >= 0.0 && (col as usize) < cols && (row as usize) < map_rows {
+                grid[row as usize][col as usize] = (c, color);
+            }
+        };
+        line(&mut plot, (0.0, left), (0.0, left + width));
+        for element in self.field.elements() {
+            if element.name == "field" {
+                continue;
+            }
+            let (x, y) = (preview::feet(element.x()), preview::feet(element.y()));
+            let (length, size) = (
+                preview::feet(element.length()),
+                preview::feet(element.width()),
+            );
+            if length > 0.0 && size > 0.0 {
+                let c = preview::corners(x, y, element.heading().radians(), length, size);
+                for i in 0..4 {
+                    line(&mut plot, c[i], c[(i + 1) % 4]);
+                }
+            } else {
+                plot(x, y, '+', Color::Grey);
+            }
+        }
+        for p in points {
+            let (c, color) = match (p.interpolated, p.reversed) {
+                (true, _) => ('.', Color::Green),
+                (false, false) => ('*', Color::Red),
+                (false, true) => ('*', Color::Yellow),
+            };
+            plot(p.x, p.y, c, color);
+        }
+        let segments = &self.defs.paths[self.path].segments;
+        for waypoint in segments.iter().flat_map(|s| &s.waypoints) {
+            let state = waypoint.state(&self.frames, &self.robot, false);
+            plot(state.x, state.y, 'o', Color::Cyan);
+        }
+        // on top, since the ends of consecutive segments coincide
+        if let Some(waypoint) = self.segment().waypoints.get(self.waypoint) {
+            let state = waypoint.state(&self.frames, &self.robot, false);
+            for i in 1..=8 {
+                let d = i as f64 * 0.25;
+                plot(
+                    state.x + d * state.t.cos(),
+                    state.y + d * state.t.sin(),
+                    '+',
+                    Color::Green,
+                );
+            }
+            plot(state.x, state.y, '@', Color::Green);
+        }
+
+        queue!(out, terminal::Clear(terminal::ClearType::All))?;
+        for (r, row) in grid.iter().enumerate() {
+            queue!(out, cursor::MoveTo(0, r as u16))?;
+            let mut color = None;
+            for &(c, cell) in row {
+                if color != Some(cell) {
+                    queue!(out, SetForegroundColor(cell))?;
+                    color = Some(cell);
+                }
+                queue!(out, Print(c))?;
+            }
+        }
+        queue!(out, ResetColor)?;
+
+        let def = &self.defs.paths[self.path];
+        let mut panel = vec![(
+            format!(
+                "{} - path {}/{} {}{}",
+                self.file.display(),
+                self.path + 1,
+                self.defs.paths.len(),
+                def.name,
+                if self.saved { "" } else { " (unsaved)" }
+            ),
+            Color::Reset,
+        )];
+        panel.push(match self.sampled {
+            Ok((ref path, ref report)) => (
+                format!(
+                    "{:.2} ft, max curvature {:.3} 1/ft, max curvature rate {:.3} 1/ft^2, {}",
+                    path.points
+                        .iter()
+                        .rfind(|p| !p.interpolated)
+                        .map_or(0.0, |p| p.distance),
+                    report.max_curvature,
+                    report.max_curvature_rate,
+                    if report.is_ok() {
+                        "within limits, no collisions".to_owned()
+                    } else {
+                        format!(
+                            "{} limits exceeded, {} collisions",
+                            report.violations.len(),
+                            report.collisions.len()
+                        )
+                    }
+                ),
+                if report.is_ok() {
+                    Color::Reset
+                } else {
+                    Color::Yellow
+                },
+            ),
+            Err(ref e) => (e.clone(), Color::Red),
+        });
+        panel.push((self.selection(), Color::Reset));
+        panel.push((self.status.clone(), Color::Reset));
+        panel.push((HELP.to_owned(), Color::DarkGrey));
+        for (i, (text, color)) in panel.into_iter().enumerate() {
+            let text: String = text.chars().take(cols).collect();
+            queue!(
+                out,
+                cursor::MoveTo(0, map_rows as u16 + i as u16),
+                SetForegroundColor(color),
+                Print(text),
+                ResetColor
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The selected waypoint or span with its values, the selected one in brackets.
+    fn selection(&self) -> String {
+        let segment = self.segment();
+        let mark = |i: usize, text: String| {
+            if i == self.value {
+                format!("[{}]", text)
+            } else {
+                text
+            }
+        };
+        let step = if self.fine { " fine" } else { "" };
+        match self.mode {
+            Mode::Waypoint => {
+                let w = match segment.waypoints.get(self.waypoint) {
+                    Some(w) => w,
+                    None => return format!("segment {} has no waypoints", self.segment),
+                };
+                let values = [w.x, w.y, w.heading, w.curvature];
+                let shown: Vec<String> = WAYPOINT_VALUES
+                    .iter()
+                    .zip(&values)
+                    .enumerate()
+                    .map(|(i, ((name, unit, _), v))| mark(i, format!("{} {} {}", name, v, unit)))
+                    .collect();
+                format!(
+                    "segment {} waypoint {} in {:?}{}: {}",
+                    self.segment,
+                    self.waypoint,
+                    w.frame,
+                    step,
+                    shown.join("  ")
+                )
+            }
+            Mode::Eta => {
+                let shown = match segment.eta.get(self.span()) {
+                    Some(params) => params
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| mark(i, v.to_string()))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    None => segment
+                        .spline
+                        .clone()
+                        .map_or("no parameters".to_owned(), |s| format!("{} spline", s)),
+                };
+                format!(
+                    "segment {} span {}{}: eta {}",
+                    self.segment,
+                    self.span(),
+                    step,
+                    shown
+                )
+            }
+        }
+    }
+}
+
+/// Whether there is a path, and every path has a segment to select.
+fn editable(defs: &DefFile) -> bool {
+    !defs.paths.is_empty() && defs.paths.iter().all(|p| !p.segments.is_empty())
+}
+
+/// The terminal in raw mode on the alternate screen for as long as it lives. Dropping it puts the
+/// terminal back, however the editor stops, so a failed draw or a panic doesn't leave the shell
+/// unusable.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        // from here on dropping the guard undoes whatever was done
+        let guard = RawTerminal;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn run(editor: &mut Editor, out: &mut impl Write) -> io::Result<()> {
+    let _terminal = RawTerminal::enter()?;
+    loop {
+        editor.draw(out)?;
+        out.flush()?;
+        if let Event::Key(key) = event::read()? {
+            // some terminals also report releases
+            if key.kind == KeyEventKind::Press && !editor.key(key) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Edits the paths in the definition file `file` for the robot profile `robot`, starting on the
+/// path `start` if given.
+pub fn edit(file: &Path, field: &str, robot: &str, start: Option<&str>) -> bool {
+    let loaded = Field::load(Path::new(field))
+        .map_err(|e| e.to_string())
+        .and_then(|field| {
+            let robot = Robot::load(Path::new(robot)).map_err(|e| e.to_string())?;
+            let frames = autos::frames(&field, &robot).map_err(|e| e.to_string())?;
+            let defs = DefFile::read(file).map_err(|e| e.to_string())?;
+            Ok((field, robot, frames, defs))
+        });
+    let (field, robot, frames, defs) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+    let path = match start {
+        Some(name) => match defs.paths.iter().position(|p| p.name == name) {
+            Some(i) => i,
+            None => {
+                eprintln!("{}: no path named {}", file.display(), name);
+                return false;
+            }
+        },
+        None => 0,
+    };
+    if !editable(&defs) {
+        eprintln!("{}: no paths to edit", file.display());
+        return false;
+    }
+
+    let mut editor = Editor {
+        file: file.to_owned(),
+        defs,
+        field,
+        robot,
+        frames,
+        path,
+        segment: 0,
+        waypoint: 0,
+        mode: Mode::Waypoint,
+        value: 0,
+        fine: false,
+        saved: true,
+        quitting: false,
+        status: String::new(),
+        sampled: Err(String::new()),
+    };
+    editor.select_path(path);
+
+    let mut out = io::stdout();
+    match run(&mut editor, &mut out) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("{}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Why a path or pose could not be generated or written.
+#[derive(Debug)]
+pub enum ExportError {
+    /// A segment's curve could not be built through its waypoints.
+    InvalidSpline {
+        segment: usize,
+    },
+    /// A marker is placed at a waypoint that is not in the chain.
+    MissingWaypoint {
+        marker: String,
+        segment: usize,
+        index: usize,
+    },
+    /// Too few distinct driven points to time parameterize the path.
+    TooShort {
+        points: usize,
+    },
+    /// The path breaks the robot's limits or runs into the field this many times, and generation
+    /// is strict.
+    Infeasible {
+        violations: usize,
+    },
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    MissingOutputDir(PathBuf),
+    /// A routine is meant to drive these paths, but they aren't defined.
+    MissingLegs {
+        legs: Vec<String>,
+    },
+}
+
+impl ExportError {
+    /// Attaches the file being written to an I/O error.
+    pub fn io(path: &Path, error: io::Error) -> Self {
+        ExportError::Io {
+            path: path.to_owned(),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::InvalidSpline { segment } => write!(
+                f,
+                "segment {} can't be built through its waypoints with its spline",
+                segment
+            ),
+            ExportError::MissingWaypoint {
+                marker,
+                segment,
+                index,
+            } => write!(
+                f,
+                "marker {} refers to missing waypoint {} of segment {}",
+                marker, index, segment
+            ),
+            ExportError::TooShort { points } => write!(
+                f,
+                "path has {} distinct driven points, too few to time parameterize",
+                points
+            ),
+            ExportError::Infeasible { violations } => write!(
+                f,
+                "path exceeds the robot's limits or runs into the field in {} places",
+                violations
+            ),
+            ExportError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ExportError::MissingOutputDir(dir) => {
+                write!(f, "output directory {} does not exist", dir.display())
+            }
+            ExportError::MissingLegs { legs } => write!(f, "no path {}", legs.join(" or ")),
+        }
+    }
+}
+
+impl Error for ExportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExportError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use crate::error::ExportError;
+use crate::path::SampledPath;
+use crate::trajectory::{self, TrajectoryConfig};
+use crate::FEET_PER_METER;
+use coord_frames::PointData;
+use dim::si;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes generated paths and poses into `dir` in one output format. Paths are exported from
+/// several threads at once.
+pub trait Exporter: Sync {
+    /// The name of the file `export_path` writes for the path `name`, relative to `dir`.
+    fn path_file(&self, name: &str) -> String;
+
+    fn export_path(&self, path: &SampledPath, name: &str, dir: &Path) -> Result<(), ExportError>;
+
+    /// Formats that only describe paths have nothing to write for a pose.
+    fn export_pose(&self, _pose: PointData, _name: &str, _dir: &Path) -> Result<(), ExportError> {
+        Ok(())
+    }
+}
+
+/// Fails up front when `dir` is missing, rather than once per file written into it.
+pub fn check_output_dir(dir: &Path) -> Result<(), ExportError> {
+    if dir.is_dir() {
+        Ok(())
+    } else {
+        Err(ExportError::MissingOutputDir(dir.to_owned()))
+    }
+}
+
+/// Creates `file` and writes it with `write`, naming the file in any error.
+fn write_file<F>(file: &Path, write: F) -> Result<(), ExportError>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    File::create(file)
+        .and_then(|f| {
+            let mut out = BufWriter::new(f);
+            write(&mut out)?;
+            out.flush()
+        })
+        .map_err(|e| ExportError::io(file, e))
+}
+
+fn write_bytes(file: &Path, contents: impl AsRef<[u8]>) -> Result<(), ExportError> {
+    fs::write(file, contents).map_err(|e| ExportError::io(file, e))
+}
+
+/// A pose the way the robot code sees it, in feet and radians.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RobotPose {
+    pub x: f64,
+    pub y: f64,
+    pub heading: f64,
+}
+
+impl From<PointData> for RobotPose {
+    fn from(point: PointData) -> Self {
+        Self {
+            x: *(point.x() / si::M) * FEET_PER_METER,
+            y: *(point.y() / si::M) * FEET_PER_METER,
+            heading: point.rot().radians(),
+        }
+    }
+}
+
+fn flag(b: bool) -> &'static str {
+    if b {
+        "True"
+    } else {
+        "False"
+    }
+}
+
+/// The `.114path` CSV read by the robot code's path follower, with any markers in a
+/// `.markers.csv` sidecar so existing readers of the path itself are unaffected.
+pub struct Path114;
+
+impl Exporter for Path114 {
+    fn path_file(&self, name: &str) -> String {
+        format!("{}.114path", name)
+    }
+
+    fn export_path(&self, path: &SampledPath, name: &str, dir: &Path) -> Result<(), ExportError> {
+        write_file(&dir.join(self.path_file(name)), |out| {
+            let mut wtr = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(out);
+            wtr.write_record([
+                "x",
+                "y",
+                "distanceSoFar",
+                "isEndPointInterpolation",
+                "isReversed",
+            ])?;
+            for point in &path.points {
+                wtr.serialize((
+                    point.x,
+                    point.y,
+                    point.distance,
+                    flag(point.interpolated),
+                    flag(point.reversed),
+                ))?;
+            }
+
+            // the footer is shorter than the rows, which the writer complains about once written
+            if let Err(e) = wtr.serialize(path.end_direction) {
+                match e.kind() {
+                    csv::ErrorKind::UnequalLengths { .. } => (),
+                    _ => return Err(e.into()),
+                }
+            }
+            wtr.flush()
+        })?;
+
+        if !path.markers.is_empty() {
+            write_file(&dir.join(format!("{}.markers.csv", name)), |out| {
+                let mut wtr = csv::Writer::from_writer(out);
+                wtr.write_record(["name", "distanceSoFar"])?;
+                for marker in &path.markers {
+                    wtr.serialize((&marker.name, marker.distance))?;
+                }
+                wtr.flush()
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Java declarations to paste into robot code: a `Pose` per pose in `<name>.pose.java`, and the
+/// `{x, y, distanceSoFar}` samples of each path as a `double[][]` in `<name>.path.java`.
+pub struct JavaSource;
+
+impl Exporter for JavaSource {
+    fn path_file(&self, name: &str) -> String {
+        format!("{}.path.java", name)
+    }
+
+    fn export_path(&self, path: &SampledPath, name: &str, dir: &Path) -> Result<(), ExportError> {
+        write_file(&dir.join(self.path_file(name)), |out| {
+            writeln!(out, "public static final double[][] {} = {{", name)?;
+            for point in path.points.iter().filter(|p| !p.interpolated) {
+                writeln!(out, "    {{{}, {}, {}}},", point.x, point.y, point.distance)?;
+            }
+            writeln!(out, "}};")
+        })
+    }
+
+    fn export_pose(&self, pose: PointData, name: &str, dir: &Path) -> Result<(), ExportError> {
+        let pose = RobotPose::from(pose);
+        write_bytes(
+            &dir.join(format!("{}.pose.java", name)),
+            format!(
+                "public static Pose {} = new Pose({}, {}, {}, 0.0);",
+                name, pose.x, pose.y, pose.heading
+            ),
+        )
+    }
+}
+
+/// The sampled path or pose as JSON, in feet.
+pub struct Json;
+
+impl Exporter for Json {
+    fn path_file(&self, name: &str) -> String {
+        format!("{}.path.json", name)
+    }
+
+    fn export_path(&self, path: &SampledPath, name: &str, dir: &Path) -> Result<(), ExportError> {
+        write_file(&dir.join(self.path_file(name)), |out| {
+            Ok(serde_json::to_writer(out, path)?)
+        })
+    }
+
+    fn export_pose(&self, pose: PointData, name: &str, dir: &Path) -> Result<(), ExportError> {
+        write_file(&dir.join(format!("{}.pose.json", name)), |out| {
+            Ok(serde_json::to_writer(out, &RobotPose::from(pose))?)
+        })
+    }
+}
+
+/// A compact little-endian encoding for readers that don't want to parse text.
+///
+/// A `.114bin` path is the magic `114P`, a `u32` point count, then per point `x`, `y` and
+/// `distanceSoFar` as `f64`s and a `u8` of flags (`1` end point interpolation, `2` reversed),
+/// and finally the end direction as two `f64`s. A `.114pose` is the magic `114O` followed by
+/// `x`, `y` and heading as `f64`s.
+pub struct Binary;
+
+impl Exporter for Binary {
+    fn path_file(&self, name: &str) -> String {
+        format!("{}.114bin", name)
+    }
+
+    fn export_path(&self, path: &SampledPath, name: &str, dir: &Path) -> Result<(), ExportError> {
+        write_file(&dir.join(self.path_file(name)), |out| {
+            out.write_all(b"114P")?;
+            out.write_all(&(path.points.len() as u32).to_le_bytes())?;
+            for point in &path.points {
+                out.write_all(&point.x.to_le_bytes())?;
+                out.write_all(&point.y.to_le_bytes())?;
+                out.write_all(&point.distance.to_le_bytes())?;
+                let flags = point.interpolated as u8 | ((point.reversed as u8) << 1);
+                out.write_all(&[flags])?;
+            }
+            out.write_all(&path.end_direction.0.to_le_bytes())?;
+            out.write_all(&path.end_direction.1.to_le_bytes())
+        })
+    }
+
+    fn export_pose(&self, pose: PointData, name: &str, dir: &Path) -> Result<(), ExportError> {
+        let pose = RobotPose::from(pose);
+        let mut out = Vec::with_capacity(28);
+        out.extend_from_slice(b"114O");
+        for v in &[pose.x, pose.y, pose.heading] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        write_bytes(&dir.join(format!("{}.114pose", name)), out)
+    }
+}
+
+/// WPILib trajectory JSON, time parameterized with `config`.
+pub struct Wpilib {
+    pub config: TrajectoryConfig,
+}
+
+impl Exporter for Wpilib {
+    fn path_file(&self, name: &str) -> String {
+        format!("{}.wpilib.json", name)
+    }
+
+    fn export_path(&self, path: &SampledPath, name: &str, dir: &Path) -> Result<(), ExportError> {
+        let states = trajectory::parameterize(path, &self.config)?;
+        write_file(&dir.join(self.path_file(name)), |out| {
+            Ok(serde_json::to_writer(out, &states)?)
+        })
+    }
+}
+
+/// Each side's position, velocity and acceleration along the trajectory as a CSV, in meters and
+/// seconds, time parameterized with `config` for a robot with `track_width` between its sides.
+pub struct Wheels {
+    pub config: TrajectoryConfig,
+    pub track_width: f64,
+}
+
+impl Exporter for Wheels {
+    fn path_file(&self, name: &str) -> String {
+        format!("{}.wheels.csv", name)
+    }
+
+    fn export_path(&self, path: &SampledPath, name: &str, dir: &Path) -> Result<(), ExportError> {
+        let states = trajectory::parameterize(path, &self.config)?;
+        write_file(&dir.join(self.path_file(name)), |out| {
+            let mut wtr = csv::Writer::from_writer(out);
+            for wheel in trajectory::wheel_states(&states, self.track_width) {
+                wtr.serialize(wheel)?;
+            }
+            wtr.flush()
+        })
+    }
+}
@@ -0,0 +1,127 @@
+use crate::frames::PathFrame;
+use coord_frames::{Angle, FrameRegistry, Meter, TfPoint};
+use dim::si;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The field the paths are generated for, relative to the generator's working directory.
+pub const DEFAULT_FIELD: &str = "fields/2018.json";
+
+/// A season's field, as a list of named elements in the field frame.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Field {
+    pub name: String,
+    /// Where the origin is and which way the axes point, for whoever writes the next field file.
+    #[serde(default)]
+    pub frame: String,
+    elements: Vec<Element>,
+}
+
+/// One named thing on the field, in meters and degrees. Which point of the element `x` and `y`
+/// refer to is up to its description.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Element {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    x: f64,
+    y: f64,
+    /// The direction the element faces.
+    #[serde(default)]
+    heading: f64,
+    /// Size across the field, along x when the heading is 90 degrees.
+    #[serde(default)]
+    width: f64,
+    /// Size along the heading.
+    #[serde(default)]
+    length: f64,
+}
+
+#[derive(Debug)]
+pub enum FieldError {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    Duplicate(String),
+    Missing(String),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            FieldError::Json(path, e) => write!(f, "{}: {}", path.display(), e),
+            FieldError::Duplicate(name) => write!(f, "field element {} is defined twice", name),
+            FieldError::Missing(name) => write!(f, "field has no element named {}", name),
+        }
+    }
+}
+
+impl Error for FieldError {}
+
+impl Field {
+    pub fn load(path: &Path) -> Result<Self, FieldError> {
+        let bytes = fs::read(path).map_err(|e| FieldError::Io(path.to_owned(), e))?;
+        let field: Field =
+            serde_json::from_slice(&bytes).map_err(|e| FieldError::Json(path.to_owned(), e))?;
+        for (i, element) in field.elements.iter().enumerate() {
+            if field.elements[..i].iter().any(|e| e.name == element.name) {
+                return Err(FieldError::Duplicate(element.name.clone()));
+            }
+        }
+        Ok(field)
+    }
+
+    pub fn elements(&self) -> &[Element] {
+        &self.elements
+    }
+
+    pub fn element(&self, name: &str) -> Result<&Element, FieldError> {
+        self.elements
+            .iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| FieldError::Missing(name.to_owned()))
+    }
+
+    /// Places every frame that sits on a field element. Frames that don't, like the starting
+    /// positions, are left at their parent's origin for the caller to set.
+    pub fn frames(&self) -> Result<FrameRegistry<PathFrame>, FieldError> {
+        let mut frames = FrameRegistry::new();
+        for &frame in PathFrame::ALL {
+            if let Some(name) = frame.element() {
+                frames.set_origin(frame, self.element(name)?.point());
+            }
+        }
+        Ok(frames)
+    }
+}
+
+impl Element {
+    pub fn x(&self) -> Meter {
+        self.x * si::M
+    }
+
+    pub fn y(&self) -> Meter {
+        self.y * si::M
+    }
+
+    pub fn heading(&self) -> Angle {
+        Angle::from_degrees(self.heading)
+    }
+
+    pub fn width(&self) -> Meter {
+        self.width * si::M
+    }
+
+    pub fn length(&self) -> Meter {
+        self.length * si::M
+    }
+
+    /// The element's position and heading in the field frame.
+    pub fn point(&self) -> TfPoint<PathFrame> {
+        TfPoint::new(PathFrame::Field, self.x(), self.y(), self.heading())
+    }
+}
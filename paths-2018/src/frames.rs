@@ -1,29 +1,50 @@
-macro_rules! const_meter {
-    ($name:ident, $value:expr) => {
-        pub const $name: ::dimensioned::si::SI<
-            f64,
-            tarr![
-                ::dimensioned::typenum::P1,
-                ::dimensioned::typenum::Z0,
-                ::dimensioned::typenum::Z0,
-                ::dimensioned::typenum::Z0,
-                ::dimensioned::typenum::Z0,
-                ::dimensioned::typenum::Z0,
-                ::dimensioned::typenum::Z0
-            ],
-        > = ::dimensioned::si::SI {
-            value_unsafe: $value,
-            _marker: ::std::marker::PhantomData,
-        };
-    };
-}
-
 use coord_frames::*;
+use serde::{Deserialize, Serialize};
 // TODO macroify this and the impl trait
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Frames are centered on their field element and face the way it does, so `x` is in front of it
+/// and `y` to its left. Only the right side of the field has frames, the left is mirrored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[repr(usize)]
 pub enum PathFrame {
     Field,
+    Exchange,
+    DiamondPlateEnd,
+    SwitchPlate,
+    SwitchLane,
+    ScalePlate,
+    CenterStart,
+    SideStart,
+}
+
+impl PathFrame {
+    pub const ALL: &'static [PathFrame] = &[
+        PathFrame::Field,
+        PathFrame::Exchange,
+        PathFrame::DiamondPlateEnd,
+        PathFrame::SwitchPlate,
+        PathFrame::SwitchLane,
+        PathFrame::ScalePlate,
+        PathFrame::CenterStart,
+        PathFrame::SideStart,
+    ];
+
+    /// The field element a frame is placed on, for frames that come from the field file.
+    pub fn element(self) -> Option<&'static str> {
+        Some(match self {
+            PathFrame::Exchange => "exchange",
+            PathFrame::DiamondPlateEnd => "rightDiamondPlateEnd",
+            PathFrame::SwitchPlate => "rightSwitchPlate",
+            PathFrame::SwitchLane => "rightSwitchLane",
+            PathFrame::ScalePlate => "rightScalePlate",
+            PathFrame::Field | PathFrame::CenterStart | PathFrame::SideStart => return None,
+        })
+    }
+
+    /// The point at the frame's origin, facing along its `x` axis.
+    pub fn origin(self) -> TfPoint<PathFrame> {
+        TfPoint::from_raw(PointData::default(), self)
+    }
 }
 
 impl From<PathFrame> for usize {
@@ -34,12 +55,19 @@ impl From<PathFrame> for usize {
 
 impl PointHeirarchy for PathFrame {
     fn parent(&self) -> ParentFrame<Self> {
+        use self::PathFrame::*;
         match *self {
-            PathFrame::Field => ParentFrame::Root,
+            Field => ParentFrame::Root,
+            Exchange | DiamondPlateEnd | SwitchPlate | SwitchLane | ScalePlate => {
+                ParentFrame::Parent(Field)
+            }
+            // starting positions are set against the wall the robot is lined up on
+            CenterStart => ParentFrame::Parent(Exchange),
+            SideStart => ParentFrame::Parent(DiamondPlateEnd),
         }
     }
 
     fn order() -> usize {
-        1
+        PathFrame::ALL.len()
     }
 }
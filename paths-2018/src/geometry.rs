@@ -0,0 +1,79 @@
+/// Curvature along a polyline, estimated from the circle through each point and its neighbors.
+#[derive(Debug, Clone)]
+pub struct CurvatureProfile {
+    /// Distance along the polyline to each point.
+    pub distance: Vec<f64>,
+    /// Signed curvature at each point, positive turning left.
+    pub curvature: Vec<f64>,
+}
+
+/// Repeated points are skipped, since they have no direction.
+pub fn curvature_profile(points: &[(f64, f64)]) -> CurvatureProfile {
+    let mut pts: Vec<(f64, f64)> = Vec::with_capacity(points.len());
+    for &p in points {
+        match pts.last() {
+            Some(&last) if dist(last, p) < 1e-9 => (),
+            _ => pts.push(p),
+        }
+    }
+
+    let n = pts.len();
+    let mut distance = Vec::with_capacity(n);
+    let mut s = 0.0;
+    for i in 0..n {
+        if i > 0 {
+            s += dist(pts[i - 1], pts[i]);
+        }
+        distance.push(s);
+    }
+
+    let mut curvature = vec![0.0; n];
+    for i in 1..n.saturating_sub(1) {
+        let (a, b, c) = (pts[i - 1], pts[i], pts[i + 1]);
+        let cross = (b.0 - a.0) * (c.1 - b.1) - (b.1 - a.1) * (c.0 - b.0);
+        curvature[i] = 2. * cross / (dist(a, b) * dist(b, c) * dist(a, c));
+    }
+    // the ends have only one neighbor, so carry in the nearest estimate
+    if n > 2 {
+        curvature[0] = curvature[1];
+        curvature[n - 1] = curvature[n - 2];
+    }
+
+    CurvatureProfile {
+        distance,
+        curvature,
+    }
+}
+
+pub fn dist(a: (f64, f64), b: (f64, f64)) -> f64 {
+    f64::sqrt((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2))
+}
+
+impl CurvatureProfile {
+    pub fn length(&self) -> f64 {
+        self.distance.last().cloned().unwrap_or(0.0)
+    }
+
+    pub fn max_curvature(&self) -> f64 {
+        self.curvature.iter().fold(0.0, |max, k| max.max(k.abs()))
+    }
+
+    /// The change in curvature per unit distance between each pair of neighboring points.
+    pub fn curvature_rate(&self) -> Vec<f64> {
+        self.curvature
+            .windows(2)
+            .zip(self.distance.windows(2))
+            .map(|(k, s)| (k[1] - k[0]) / (s[1] - s[0]))
+            .collect()
+    }
+
+    /// The integral of the squared curvature rate, which is small for paths that ease in and out
+    /// of turns.
+    pub fn curvature_rate_energy(&self) -> f64 {
+        self.curvature_rate()
+            .iter()
+            .zip(self.distance.windows(2))
+            .map(|(dk, s)| dk * dk * (s[1] - s[0]))
+            .sum()
+    }
+}
@@ -0,0 +1,225 @@
+//! Regenerates every auto and compares it against the outputs checked in under `golden/`, so a
+//! change to a field constant or an exporter can't silently move a path.
+//!
+//! Paths defined in code are covered along with those in the definition files under
+//! `golden/defs/`. Run with `PATHS_BLESS=1` to accept the current outputs as the new golden files;
+//! an output without a golden file fails until then.
+//!
+//! Paths on eta3 splines are sampled with the quintic Hermite spline instead. The eta3 curves come
+//! from a git dependency, so the goldens pin where the waypoints are rather than that crate's
+//! output, and don't have to be regenerated whenever its revision moves.
+
+use crate::curve::Spline;
+use crate::diff::TOLERANCE;
+use crate::export::{self, Exporter};
+use crate::field::{self, Field};
+use crate::geometry::dist;
+use crate::parse;
+use crate::path::SampledPath;
+use crate::robot::{self, Robot};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Moved points listed per path, beyond which only the count is given.
+const SHOWN: usize = 5;
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("golden")
+}
+
+fn bless() -> bool {
+    std::env::var_os("PATHS_BLESS").is_some_and(|v| v != "0")
+}
+
+/// Describes how `new` differs from `old`, or `None` if they match.
+fn diff_path(old: &SampledPath, new: &SampledPath) -> Option<String> {
+    let mut out = Vec::new();
+    if old.points.len() != new.points.len() {
+        out.push(format!(
+            "{} points, was {}",
+            new.points.len(),
+            old.points.len()
+        ));
+    }
+
+    let mut moved = Vec::new();
+    let mut furthest: f64 = 0.0;
+    for (i, (a, b)) in old.points.iter().zip(&new.points).enumerate() {
+        let shift = dist((a.x, a.y), (b.x, b.y));
+        if shift > TOLERANCE
+            || (b.distance - a.distance).abs() > TOLERANCE
+            || a.interpolated != b.interpolated
+            || a.reversed != b.reversed
+        {
+            furthest = furthest.max(shift);
+            moved.push(format!(
+                "point {}: ({:.4}, {:.4}) d {:.4} -> ({:.4}, {:.4}) d {:.4}, moved {:.6} ft{}{}",
+                i,
+                a.x,
+                a.y,
+                a.distance,
+                b.x,
+                b.y,
+                b.distance,
+                shift,
+                if a.interpolated != b.interpolated {
+                    ", interpolation flag changed"
+                } else {
+                    ""
+                },
+                if a.reversed != b.reversed {
+                    ", direction changed"
+                } else {
+                    ""
+                },
+            ));
+        }
+    }
+    if !moved.is_empty() {
+        out.push(format!(
+            "{} of {} points moved, up to {:.6} ft",
+            moved.len(),
+            old.points.len().min(new.points.len()),
+            furthest
+        ));
+        let hidden = moved.len().saturating_sub(SHOWN);
+        out.extend(moved.into_iter().take(SHOWN).map(|m| format!("  {}", m)));
+        if hidden > 0 {
+            out.push(format!("  ... and {} more", hidden));
+        }
+    }
+
+    let (od, nd) = (old.end_direction, new.end_direction);
+    if (od.0 - nd.0).abs() > TOLERANCE || (od.1 - nd.1).abs() > TOLERANCE {
+        out.push(format!(
+            "end direction ({:.4}, {:.4}) -> ({:.4}, {:.4})",
+            od.0, od.1, nd.0, nd.1
+        ));
+    }
+
+    let names = |p: &SampledPath| p.markers.iter().map(|m| m.name.clone()).collect::<Vec<_>>();
+    if names(old) != names(new) {
+        out.push(format!("markers {:?} -> {:?}", names(old), names(new)));
+    } else {
+        for (a, b) in old.markers.iter().zip(&new.markers) {
+            if (a.distance - b.distance).abs() > TOLERANCE {
+                out.push(format!(
+                    "marker {} at {:.4} ft -> {:.4} ft",
+                    a.name, a.distance, b.distance
+                ));
+            }
+        }
+    }
+
+    if out.is_empty() {
+        None
+    } else {
+        Some(out.join("\n    "))
+    }
+}
+
+/// Describes how the numbers in two JSON documents differ, or `None` if they match.
+fn diff_json(old: &serde_json::Value, new: &serde_json::Value) -> Option<String> {
+    use serde_json::Value;
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) if a.len() == b.len() => {
+            let diffs: Vec<String> = a
+                .iter()
+                .filter_map(|(key, va)| match b.get(key) {
+                    Some(vb) => diff_json(va, vb).map(|d| format!("{}: {}", key, d)),
+                    None => Some(format!("{} missing", key)),
+                })
+                .collect();
+            if diffs.is_empty() {
+                None
+            } else {
+                Some(diffs.join(", "))
+            }
+        }
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            if (a - b).abs() > TOLERANCE {
+                Some(format!("{} -> {}", a, b))
+            } else {
+                None
+            }
+        }
+        _ if old == new => None,
+        _ => Some(format!("{} -> {}", old, new)),
+    }
+}
+
+/// Compares `file` in `out` against its golden copy, or installs it as the golden copy when
+/// blessing.
+fn compare(
+    file: &str,
+    out: &Path,
+    diff: impl Fn(&Path, &Path) -> Option<String>,
+) -> Option<String> {
+    let golden = golden_dir().join(file);
+    let generated = out.join(file);
+    if bless() {
+        fs::copy(&generated, &golden).unwrap();
+        return None;
+    }
+    if !golden.exists() {
+        return Some(format!("{}: no golden file", file));
+    }
+    diff(&golden, &generated).map(|d| format!("{}:\n    {}", file, d))
+}
+
+#[test]
+fn generated_paths_match_golden_files() {
+    let out = std::env::temp_dir().join(format!("paths-2018-golden-{}", std::process::id()));
+    fs::create_dir_all(&out).unwrap();
+
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let field = Field::load(&manifest.join(field::DEFAULT_FIELD)).unwrap();
+    let robot = Robot::load(&manifest.join(robot::DEFAULT_ROBOT)).unwrap();
+    let autos = crate::define_autos(&field, &robot, &golden_dir().join("defs")).unwrap();
+    let mut failures = Vec::new();
+    for (name, pose) in &autos.poses {
+        export::Json.export_pose(*pose, name, &out).unwrap();
+        let read = |file: &Path| -> serde_json::Value {
+            serde_json::from_slice(&fs::read(file).unwrap()).unwrap()
+        };
+        failures.extend(compare(&format!("{}.pose.json", name), &out, |a, b| {
+            diff_json(&read(a), &read(b))
+        }));
+    }
+    for (name, mut chain) in autos.paths {
+        let name = &name;
+        if chain
+            .segments()
+            .iter()
+            .any(|segment| matches!(segment.spline(), Spline::Eta3(_)))
+        {
+            chain.set_spline(&Spline::QuinticHermite);
+        }
+        let path = chain.sample().unwrap();
+        export::Path114.export_path(&path, name, &out).unwrap();
+        let file = export::Path114.path_file(name);
+        failures.extend(compare(&file, &out, |a, b| {
+            diff_path(
+                &parse::read_114path(a).unwrap(),
+                &parse::read_114path(b).unwrap(),
+            )
+        }));
+        // marker sidecars are compared along with their paths, so are installed with them
+        let markers = format!("{}.markers.csv", name);
+        if bless() {
+            if out.join(&markers).exists() {
+                fs::copy(out.join(&markers), golden_dir().join(&markers)).unwrap();
+            } else if golden_dir().join(&markers).exists() {
+                fs::remove_file(golden_dir().join(&markers)).unwrap();
+            }
+        }
+    }
+    fs::remove_dir_all(&out).unwrap();
+
+    assert!(
+        failures.is_empty(),
+        "generated outputs differ from golden/, rerun with PATHS_BLESS=1 if this is intended\n\n{}",
+        failures.join("\n\n")
+    );
+}
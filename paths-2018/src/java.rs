@@ -0,0 +1,154 @@
+use crate::error::ExportError;
+use crate::FEET_PER_METER;
+use coord_frames::{Axis, PointData};
+use dim::si;
+use std::fs;
+use std::path::Path;
+
+/// Generates one Java class holding every pose, its mirror image, and a registry of the generated
+/// path files, so robot code can reference them directly instead of pasting output by hand.
+pub struct JavaClass {
+    pub package: String,
+    pub class_name: String,
+    /// Fully qualified name of the robot code's `Pose` class. Without one the class declares its
+    /// own `Pose`, so it compiles on its own.
+    pub pose_import: Option<String>,
+}
+
+impl Default for JavaClass {
+    fn default() -> Self {
+        Self {
+            package: "org.usfirst.frc.team114.paths".to_owned(),
+            class_name: "AutoPaths".to_owned(),
+            pose_import: None,
+        }
+    }
+}
+
+/// Declared inside the class when robot code's own `Pose` isn't imported, with the constructor
+/// the pose literals call.
+const POSE_CLASS: &str = "    /** A pose in feet and radians. */
+    public static final class Pose {
+        public final double x;
+        public final double y;
+        public final double heading;
+        public final double curvature;
+
+        public Pose(double x, double y, double heading, double curvature) {
+            this.x = x;
+            this.y = y;
+            this.heading = heading;
+            this.curvature = curvature;
+        }
+    }
+
+";
+
+fn pose_literal(pose: PointData) -> String {
+    format!(
+        "new Pose({:?}, {:?}, {:?}, 0.0)",
+        *(pose.x() / si::M) * FEET_PER_METER,
+        *(pose.y() / si::M) * FEET_PER_METER,
+        pose.rot().radians()
+    )
+}
+
+fn string_literal(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl JavaClass {
+    /// Renders the class source. `paths` pairs each path name with its file, relative to the
+    /// output directory.
+    pub fn render(&self, poses: &[(String, PointData)], paths: &[(String, String)]) -> String {
+        let mut out = String::new();
+        out += "// Generated by paths-2018. Do not edit, rerun the generator instead.\n";
+        out += &format!("package {};\n\n", self.package);
+        out += "import java.util.Collections;\n";
+        out += "import java.util.LinkedHashMap;\n";
+        out += "import java.util.Map;\n";
+        if let Some(ref import) = self.pose_import {
+            out += &format!("import {};\n", import);
+        }
+        out += &format!("\npublic final class {} {{\n", self.class_name);
+        out += &format!("    private {}() {{}}\n\n", self.class_name);
+        if self.pose_import.is_none() {
+            out += POSE_CLASS;
+        }
+
+        for (name, pose) in poses {
+            out += &format!(
+                "    public static final Pose {} = {};\n",
+                name,
+                pose_literal(*pose)
+            );
+            out += &format!(
+                "    public static final Pose {}Mirrored = {};\n",
+                name,
+                pose_literal(pose.mirror(Axis::Y))
+            );
+        }
+
+        out += "\n    /** Path name to generated file, relative to the output directory. */\n";
+        out += "    public static final Map<String, String> PATHS;\n\n";
+        out += "    static {\n";
+        out += "        Map<String, String> paths = new LinkedHashMap<>();\n";
+        for (name, file) in paths {
+            out += &format!(
+                "        paths.put({}, {});\n",
+                string_literal(name),
+                string_literal(file)
+            );
+        }
+        out += "        PATHS = Collections.unmodifiableMap(paths);\n";
+        out += "    }\n";
+        out += "}\n";
+        out
+    }
+
+    /// Writes `<class_name>.java` into `dir`.
+    pub fn write(
+        &self,
+        poses: &[(String, PointData)],
+        paths: &[(String, String)],
+        dir: &Path,
+    ) -> Result<(), ExportError> {
+        let file = dir.join(format!("{}.java", self.class_name));
+        fs::write(&file, self.render(poses, paths)).map_err(|e| ExportError::io(&file, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_the_robot_pose() {
+        let poses = vec![(
+            "centerStart".to_owned(),
+            PointData::xyr(1., 2., Default::default()),
+        )];
+        let paths = vec![("out".to_owned(), "out.114path".to_owned())];
+        let class = JavaClass {
+            pose_import: Some("com.example.robot.Pose".to_owned()),
+            ..JavaClass::default()
+        };
+        let source = class.render(&poses, &paths);
+        assert!(source.contains("\nimport com.example.robot.Pose;\n"));
+        assert!(!source.contains("class Pose"));
+        assert!(source.contains("public static final Pose centerStart = new Pose("));
+        assert!(source.contains("paths.put(\"out\", \"out.114path\");"));
+    }
+
+    #[test]
+    fn declares_a_pose_without_an_import() {
+        let poses = vec![("start".to_owned(), PointData::default())];
+        let source = JavaClass::default().render(&poses, &[]);
+        assert!(!source.contains("Pose;\n"));
+        assert!(source.contains("public static final class Pose {"));
+        assert!(
+            source.contains("public Pose(double x, double y, double heading, double curvature)")
+        );
+        assert!(source.contains("public static final Pose start = new Pose(0.0, 0.0, 0.0, 0.0);"));
+    }
+}
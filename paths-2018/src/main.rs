@@ -1,286 +1,832 @@
 extern crate coord_frames;
-use coord_frames::*;
-#[macro_use]
+extern crate csv;
 extern crate dimensioned as dim;
-use self::dim::si;
+extern crate eta3_spline;
+extern crate serde_json;
 
-#[macro_use]
+mod approach;
+mod autos;
+mod batch;
+mod curve;
+mod defs;
+mod diff;
+mod edit;
+mod error;
+mod export;
+mod field;
 mod frames;
-use self::frames::PathFrame;
-
-const_meter!(BASELINE_TO_SWITCH_NEAR, 3.556);
-const_meter!(BASELINE_TO_SWITCH_FAR, 4.9784);
-const_meter!(BASELINE_TO_SCALE_PLATE_EDGE, 7.61111);
-const_meter!(BASELINE_TO_PLATFORM, 6.641338);
+mod geometry;
+#[cfg(test)]
+mod golden;
+mod java;
+mod optimize;
+mod parse;
+mod path;
+mod preview;
+mod robot;
+mod routines;
+mod serve;
+mod sim;
+mod trajectory;
+mod validate;
+mod watch;
+use self::batch::PathOutcome;
+use self::curve::Spline;
+use self::error::ExportError;
+use self::export::Exporter;
+use self::field::Field;
+use self::java::JavaClass;
+use self::optimize::{Constraints, Objective};
+use self::path::PathChain;
+use self::robot::Robot;
+use self::sim::{Controller, SimConfig};
+use coord_frames::PointData;
+use dim::si;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-const_meter!(MIDLINE_TO_EXCHANGE_EDGE, 0.3048);
-const_meter!(MIDLINE_TO_DIAMOND_PLATE_END, 3.3528);
+/// The robot code works in feet.
+pub const FEET_PER_METER: f64 = 3.28084;
 
-const_meter!(WALL_TO_SCALE_PLATE_EDGE, 1.82245);
-const_meter!(WALL_TO_SWITCH_EDGE, 2.16535);
+const FORMATS: &[&str] = &["114path", "java", "json", "binary", "wpilib", "wheels"];
+const DEFAULT_FORMATS: &[&str] = &["114path", "java"];
 
-const_meter!(SCALE_PLATE_WIDTH, 0.9144);
-const_meter!(SCALE_PLATE_LENGTH, 1.2192);
+fn exporter(format: &str, robot: &Robot) -> Option<Box<dyn Exporter>> {
+    Some(match format {
+        "114path" => Box::new(export::Path114),
+        "java" => Box::new(export::JavaSource),
+        "json" => Box::new(export::Json),
+        "binary" => Box::new(export::Binary),
+        "wpilib" => Box::new(export::Wpilib {
+            config: robot.trajectory(),
+        }),
+        "wheels" => Box::new(export::Wheels {
+            config: robot.trajectory(),
+            track_width: robot.drive().track_width,
+        }),
+        _ => return None,
+    })
+}
 
-const_meter!(FIELD_WIDTH, 8.2296);
+fn usage() -> ! {
+    eprintln!(
+        "usage: paths-2018 [--format <{}|all>[,...]] [--java-package <package>] \
+         [--java-class <name>] [--java-pose-import <class>] [--strict] \
+         [--spline <{}>] [--field <file.json>] [--robot <file.json>] [--paths <dir>] \
+         [--preview] [--jobs <n>]\n       \
+         paths-2018 watch [<options>]\n       \
+         paths-2018 edit <paths.json> [--path <name>] [--field <file.json>] \
+         [--robot <file.json>]\n       \
+         paths-2018 check <file.114path>...\n       \
+         paths-2018 diff (<old-dir> | --rev <revision>) [<new-dir>]\n       \
+         paths-2018 field [<file.json>]\n       \
+         paths-2018 serve [--port <port>] [--field <file.json>] [--robot <file.json>]\n       \
+         paths-2018 optimize [--objective <{}>] [--max-curvature <1/ft>] \
+         [--field <file.json>] [--robot <file.json>] [--paths <dir>] [<path>...]\n       \
+         paths-2018 simulate <file.114path> [--controller <{}>] [--lookahead <m>] \
+         [--latency <s>] [--slip <fraction>] [--trace <file.csv>] [--robot <file.json>]",
+        FORMATS.join("|"),
+        Spline::NAMES.join("|"),
+        Objective::NAMES.join("|"),
+        Controller::NAMES.join("|")
+    );
+    std::process::exit(2)
+}
 
-// TODO update this
-const_meter!(ROBOT_LENGTH, 0.9525);
-const_meter!(ROBOT_WIDTH, 0.8636);
+/// Settings for a run, from the command line.
+struct Options {
+    /// Paths and poses are written by each of these, in order.
+    exporters: Vec<Box<dyn Exporter>>,
+    /// The names of the exporters, to set them up again for a reloaded robot.
+    formats: Vec<String>,
+    java: JavaClass,
+    /// Whether paths that break the robot's limits or run into the field fail generation.
+    strict: bool,
+    /// Replaces the spline of every segment, to compare curve families.
+    spline: Option<Spline>,
+    field: String,
+    robot_file: String,
+    robot: Robot,
+    /// Where path definition files are read from.
+    paths: PathBuf,
+    /// Whether to draw each path over the field as well.
+    preview: bool,
+    /// How many paths to generate at once.
+    jobs: usize,
+}
 
-use std::f64::consts::PI;
+fn options_from_args(mut args: impl Iterator<Item = String>) -> Options {
+    let mut formats: Vec<&str> = DEFAULT_FORMATS.to_vec();
+    let mut java = JavaClass::default();
+    let mut names = String::new();
+    let mut strict = false;
+    let mut spline = None;
+    let mut field = field::DEFAULT_FIELD.to_owned();
+    let mut robot = robot::DEFAULT_ROBOT.to_owned();
+    let mut paths = PathBuf::from(defs::DEFAULT_DIR);
+    let mut preview = false;
+    let mut jobs = batch::default_threads();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--format" => names = value(),
+            "--java-package" => java.package = value(),
+            "--java-class" => java.class_name = value(),
+            "--java-pose-import" => java.pose_import = Some(value()),
+            "--strict" => strict = true,
+            "--spline" => spline = Some(Spline::from_name(&value()).unwrap_or_else(|| usage())),
+            "--field" => field = value(),
+            "--robot" => robot = value(),
+            "--paths" => paths = PathBuf::from(value()),
+            "--preview" => preview = true,
+            "--jobs" => jobs = value().parse().unwrap_or_else(|_| usage()),
+            _ => usage(),
+        }
+    }
+    if !names.is_empty() {
+        formats = names
+            .split(',')
+            .flat_map(|name| match name {
+                "all" => FORMATS.to_vec(),
+                _ => vec![name],
+            })
+            .collect();
+        // keep the first of each, since robot code loads paths in the first format asked for
+        let mut seen = BTreeSet::new();
+        formats.retain(|format| seen.insert(*format));
+    }
+    let robot_file = robot;
+    let robot = load_robot(&robot_file).unwrap_or_else(|| std::process::exit(1));
+    let exporters = formats
+        .iter()
+        .map(|format| exporter(format, &robot).unwrap_or_else(|| usage()))
+        .collect();
+    Options {
+        exporters,
+        formats: formats.into_iter().map(str::to_owned).collect(),
+        java,
+        strict,
+        spline,
+        field,
+        robot_file,
+        robot,
+        paths,
+        preview,
+        jobs,
+    }
+}
 
-const PI2: f64 = PI / 2.0;
+impl Options {
+    /// Swaps in a reloaded robot profile, setting the exporters up again for it.
+    fn set_robot(&mut self, robot: Robot) {
+        self.exporters = self
+            .formats
+            .iter()
+            .filter_map(|format| exporter(format, &robot))
+            .collect();
+        self.robot = robot;
+    }
+}
 
 fn main() {
-    // create all things on the right, mirror for the left
-    let center_start = TfPoint::new(
-        PathFrame::Field,
-        ROBOT_WIDTH / 2. - MIDLINE_TO_EXCHANGE_EDGE,
-        ROBOT_LENGTH / 2.,
-        PI2,
-    );
-    let side_start = TfPoint::new(
-        PathFrame::Field,
-        MIDLINE_TO_DIAMOND_PLATE_END - ROBOT_WIDTH / 2.,
-        ROBOT_LENGTH / 2.,
-        PI2,
-    );
+    let mut args = std::env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("check") => {
+            let files: Vec<String> = args.skip(1).collect();
+            if files.is_empty() {
+                usage();
+            }
+            if !check(&files) {
+                std::process::exit(1);
+            }
+        }
+        Some("diff") => {
+            args.next();
+            if !diff(args) {
+                std::process::exit(1);
+            }
+        }
+        Some("edit") => {
+            args.next();
+            if !edit(args) {
+                std::process::exit(1);
+            }
+        }
+        Some("field") => {
+            let file = args
+                .nth(1)
+                .unwrap_or_else(|| field::DEFAULT_FIELD.to_owned());
+            if !describe_field(&file) {
+                std::process::exit(1);
+            }
+        }
+        Some("optimize") => {
+            args.next();
+            if !optimize(args) {
+                std::process::exit(1);
+            }
+        }
+        Some("serve") => {
+            args.next();
+            if !serve(args) {
+                std::process::exit(1);
+            }
+        }
+        Some("simulate") => {
+            args.next();
+            if !simulate(args) {
+                std::process::exit(1);
+            }
+        }
+        Some("watch") => {
+            args.next();
+            let options = Options {
+                preview: true,
+                ..options_from_args(args)
+            };
+            if !watch::watch(options) {
+                std::process::exit(1);
+            }
+        }
+        _ => {
+            if !generate(options_from_args(args)) {
+                std::process::exit(1);
+            }
+        }
+    }
+}
 
-    export_pose(center_start.raw_data(), "centerStart");
-    export_pose(side_start.raw_data(), "rightSideStart");
-    export_pose(side_start.raw_data().mirror(Axis::Y), "leftSideStart");
+/// Reads back existing `.114path` files and summarizes them, returning whether all were valid.
+fn check(files: &[String]) -> bool {
+    let mut ok = true;
+    for file in files {
+        match parse::read_114path(Path::new(file)) {
+            Ok(path) => {
+                let driven = path.points.iter().filter(|p| !p.interpolated);
+                let length = driven.clone().next_back().map_or(0.0, |p| p.distance);
+                println!(
+                    "{}: {} points, {:.2} ft, {} reversed, {} markers, ends toward ({:.3}, {:.3})",
+                    file,
+                    path.points.len(),
+                    length,
+                    driven.filter(|p| p.reversed).count(),
+                    path.markers.len(),
+                    path.end_direction.0,
+                    path.end_direction.1
+                );
+            }
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
 
-    let near_switch = TfPoint::new(
-        PathFrame::Field,
-        FIELD_WIDTH / 2. - WALL_TO_SWITCH_EDGE - SCALE_PLATE_WIDTH / 2.,
-        BASELINE_TO_SWITCH_NEAR - ROBOT_LENGTH / 2.,
-        PI2,
-    );
-    let far_switch = near_switch.mirror(Axis::Y);
-
-    // near side switch but approaches from the axis of the switch
-    let near_switch_outside = TfPoint::new(
-        PathFrame::Field,
-        FIELD_WIDTH / 2. - WALL_TO_SWITCH_EDGE + ROBOT_LENGTH / 2.,
-        BASELINE_TO_SWITCH_NEAR + SCALE_PLATE_LENGTH / 2.,
-        PI,
-    );
+/// Compares the outputs in `out`, or another directory, against those of an earlier run or of
+/// another revision.
+fn diff(mut args: impl Iterator<Item = String>) -> bool {
+    let mut dirs = Vec::new();
+    let mut rev = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rev" => rev = Some(args.next().unwrap_or_else(|| usage())),
+            _ => dirs.push(PathBuf::from(arg)),
+        }
+    }
+    // the arguments are checked before checking anything out, since exiting skips the cleanup
+    let (old, new) = match (&rev, dirs.as_slice()) {
+        (Some(_), [new]) => (None, new.clone()),
+        (Some(_), []) => (None, PathBuf::from("out")),
+        (None, [old, new]) => (Some(old.clone()), new.clone()),
+        (None, [old]) => (Some(old.clone()), PathBuf::from("out")),
+        _ => usage(),
+    };
+    let checkout = match rev {
+        Some(ref rev) => match diff::Checkout::generate(rev) {
+            Ok(checkout) => Some(checkout),
+            Err(e) => {
+                eprintln!("{}", e);
+                return false;
+            }
+        },
+        None => None,
+    };
+    let old = match (old, &checkout) {
+        (Some(old), _) => old,
+        (None, Some(checkout)) => checkout.out_dir.clone(),
+        (None, None) => unreachable!(),
+    };
+    match diff::report(&old, &new) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("{}", e);
+            false
+        }
+    }
+}
 
-    // faces from the outside looking in
-    let near_scale = TfPoint::new(
-        PathFrame::Field,
-        FIELD_WIDTH / 2. - WALL_TO_SCALE_PLATE_EDGE + ROBOT_LENGTH / 2. + 0.254 * si::M,
-        BASELINE_TO_SCALE_PLATE_EDGE + SCALE_PLATE_LENGTH / 2. - 0.1 * si::M,
-        PI + 0.254,
-    );
-    // comes in facing forward
-    let far_scale = TfPoint::new(
-        PathFrame::Field,
-        -(FIELD_WIDTH / 2. - WALL_TO_SCALE_PLATE_EDGE),
-        BASELINE_TO_SCALE_PLATE_EDGE - ROBOT_LENGTH / 2. - 0.4 * si::M,
-        4.3 * PI / 10.,
-    );
+/// Searches eta parameters for each segment of the named paths, or all of them, and prints them
+/// ready to paste into the path definitions.
+fn optimize(mut args: impl Iterator<Item = String>) -> bool {
+    let mut objective = Objective::CurvatureRate;
+    let mut constraints = Constraints::default();
+    let mut field = field::DEFAULT_FIELD.to_owned();
+    let mut robot = robot::DEFAULT_ROBOT.to_owned();
+    let mut paths = PathBuf::from(defs::DEFAULT_DIR);
+    let mut names = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--objective" => objective = Objective::from_name(&value()).unwrap_or_else(|| usage()),
+            "--max-curvature" => {
+                constraints.max_curvature = Some(value().parse().unwrap_or_else(|_| usage()))
+            }
+            "--field" => field = value(),
+            "--robot" => robot = value(),
+            "--paths" => paths = PathBuf::from(value()),
+            _ => names.push(arg),
+        }
+    }
 
-    let gen_center_switch = || {
-        export_path(
-            vec![
-                zero_kappa(center_start.raw_data()),
-                zero_kappa(far_switch.raw_data()),
-            ],
-            vec![basic_param(10.0)],
-            "centerToLeftSwitch",
-            300,
-        );
-        export_path(
-            vec![
-                zero_kappa(center_start.raw_data()),
-                zero_kappa(near_switch.raw_data()),
-            ],
-            vec![basic_param(10.0)],
-            "centerToRightSwitch",
-            300,
-        );
+    let loaded = load_field(&field).zip(load_robot(&robot));
+    let autos = match loaded.and_then(|(field, robot)| define_autos(&field, &robot, &paths)) {
+        Some(autos) => autos,
+        None => return false,
     };
-    gen_center_switch();
-
-    let gen_side_scale = || {
-        use self::Axis::Y;
-        // near scales
-        export_path(
-            vec![
-                zero_kappa(side_start.raw_data()),
-                with_kappa(near_scale.raw_data(), 2., 0.),
-            ],
-            vec![EtaParam::new(5., 10.0, 0., 25., 0., 0.)],
-            "rightToRightScale",
-            400,
-        );
-        export_path(
-            vec![
-                zero_kappa(side_start.raw_data().mirror(Y)),
-                zero_kappa(near_scale.raw_data().mirror(Y)),
-            ],
-            vec![EtaParam::new(5., 10.0, 0., 25., 0., 0.)],
-            "leftToLeftScale",
-            400,
-        );
-        // far scales
-        export_path(
-            vec![
-                zero_kappa(side_start.raw_data()),
-                zero_kappa(
-                    TfPoint::new(
-                        PathFrame::Field,
-                        FIELD_WIDTH / 2. - WALL_TO_SWITCH_EDGE / 2.,
-                        BASELINE_TO_SWITCH_FAR,
-                        PI2,
+    for (name, chain) in &autos.paths {
+        if !names.is_empty() && !names.contains(name) {
+            continue;
+        }
+        for (i, segment) in chain.segments().iter().enumerate() {
+            let params = match segment.spline() {
+                Spline::Eta3(params) => params,
+                spline => {
+                    println!(
+                        "{}, segment {}: {} spline has no parameters",
+                        name,
+                        i,
+                        spline.name()
+                    );
+                    continue;
+                }
+            };
+            let states = segment.travel_states();
+            let before = optimize::score(&states, params, objective)
+                .map_or("invalid".to_owned(), |s| format!("{:.4}", s));
+            let best = optimize::optimize(&states, objective, constraints);
+            println!(
+                "{}, segment {}: {} {} -> {:.4}{}",
+                name,
+                i,
+                objective.name(),
+                before,
+                best.score,
+                if best.violation > 0.0 {
+                    format!(", {:.4} 1/ft over the curvature limit", best.violation)
+                } else {
+                    String::new()
+                }
+            );
+            // ready to paste into the segment in its definition file
+            let params: Vec<String> = best
+                .params
+                .iter()
+                .map(|e| {
+                    format!(
+                        "[{:.3}, {:.3}, {:.3}, {:.3}, {:.3}, {:.3}]",
+                        e[0], e[1], e[2], e[3], e[4], e[5]
                     )
-                    .raw_data(),
-                ),
-                with_kappa(far_scale.raw_data(), 0., 0.),
-            ],
-            vec![basic_param(5.0), EtaParam::new(15.0, 12.5, 0., 0., 0., 0.)],
-            "rightToLeftScale",
-            400,
-        );
-        export_path(
-            vec![
-                zero_kappa(side_start.raw_data().mirror(Y)),
-                zero_kappa(
-                    TfPoint::new(
-                        PathFrame::Field,
-                        FIELD_WIDTH / 2. - WALL_TO_SWITCH_EDGE / 2.,
-                        BASELINE_TO_SWITCH_FAR,
-                        PI2,
-                    )
-                    .raw_data()
-                    .mirror(Y),
-                ),
-                with_kappa(far_scale.raw_data().mirror(Y), 0., 0.),
-            ],
-            vec![basic_param(5.0), EtaParam::new(15.0, 12.5, 0., 0., 0., 0.)],
-            "leftToRightScale",
-            400,
-        );
+                })
+                .collect();
+            println!("    \"eta\": [{}]", params.join(", "));
+        }
+    }
+    true
+}
+
+/// Follows a generated path with a simulated robot and reports how closely it tracked.
+fn simulate(mut args: impl Iterator<Item = String>) -> bool {
+    let file = args.next().unwrap_or_else(|| usage());
+    let mut controller = Controller::from_name("ramsete").unwrap();
+    let mut latency = 0.0;
+    let mut slip = 0.0;
+    let mut lookahead = None;
+    let mut trace = None;
+    let mut robot = robot::DEFAULT_ROBOT.to_owned();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        let mut number = || value().parse().unwrap_or_else(|_| usage());
+        match arg.as_str() {
+            "--controller" => {
+                controller = Controller::from_name(&value()).unwrap_or_else(|| usage())
+            }
+            "--lookahead" => lookahead = Some(number()),
+            "--latency" => latency = number(),
+            "--slip" => slip = number(),
+            "--trace" => trace = Some(value()),
+            "--robot" => robot = value(),
+            _ => usage(),
+        }
+    }
+    if let (Controller::PurePursuit { .. }, Some(lookahead)) = (controller, lookahead) {
+        controller = Controller::PurePursuit { lookahead };
+    }
+    // losing all of the wheels' motion, the robot would never move
+    if !(0.0..1.0).contains(&slip) {
+        eprintln!("slip must be at least 0 and less than 1, got {}", slip);
+        return false;
+    }
+    let robot = match load_robot(&robot) {
+        Some(robot) => robot,
+        None => return false,
     };
-    gen_side_scale();
-
-    // near switch
-    let gen_near_switch = || {
-        use self::Axis::Y;
-        export_path(
-            vec![
-                zero_kappa(side_start.raw_data()),
-                zero_kappa(near_switch_outside.raw_data()),
-            ],
-            vec![basic_param(10.0)],
-            "rightToRightSwitch",
-            300,
-        );
-        export_path(
-            vec![
-                zero_kappa(side_start.raw_data().mirror(Y)),
-                zero_kappa(near_switch_outside.raw_data().mirror(Y)),
-            ],
-            vec![basic_param(10.0)],
-            "leftToLeftSwitch",
-            300,
-        );
+    let config = SimConfig {
+        drive: robot.drive(),
+        controller,
+        latency,
+        slip,
+    };
+
+    let path = match parse::read_114path(Path::new(&file)) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("{}: {}", file, e);
+            return false;
+        }
+    };
+    let result = match sim::simulate(&path, &robot.trajectory(), &config) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}: {}", file, e);
+            return false;
+        }
+    };
+    println!(
+        "{}: {}, {} (trajectory {:.2} s), cross-track error max {:.3} m rms {:.3} m, \
+         final error {:.3} m {:.1} deg",
+        file,
+        config.controller.name(),
+        result
+            .completion_time
+            .map_or("did not finish".to_owned(), |t| format!(
+                "finished in {:.2} s",
+                t
+            )),
+        result.trajectory_time,
+        result.max_cross_track_error,
+        result.rms_cross_track_error,
+        result.final_position_error,
+        result.final_heading_error.degrees()
+    );
+    if let Some(trace) = trace {
+        if let Err(e) = sim::write_trace(&result, Path::new(&trace)) {
+            eprintln!("{}", e);
+            return false;
+        }
+    }
+    true
+}
+
+/// Opens a path definition file in the terminal editor.
+fn edit(mut args: impl Iterator<Item = String>) -> bool {
+    let file = args.next().unwrap_or_else(|| usage());
+    let mut field = field::DEFAULT_FIELD.to_owned();
+    let mut robot = robot::DEFAULT_ROBOT.to_owned();
+    let mut start = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--path" => start = Some(value()),
+            "--field" => field = value(),
+            "--robot" => robot = value(),
+            _ => usage(),
+        }
+    }
+    edit::edit(Path::new(&file), &field, &robot, start.as_deref())
+}
+
+/// Serves the generated paths as a web page.
+fn serve(mut args: impl Iterator<Item = String>) -> bool {
+    let mut port = serve::DEFAULT_PORT;
+    let mut field = field::DEFAULT_FIELD.to_owned();
+    let mut robot = robot::DEFAULT_ROBOT.to_owned();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--port" => port = value().parse().unwrap_or_else(|_| usage()),
+            "--field" => field = value(),
+            "--robot" => robot = value(),
+            _ => usage(),
+        }
+    }
+    serve::serve(Path::new("out"), &field, &robot, port)
+}
+
+/// Lists the elements of a field file, to check one being written for a new season.
+fn describe_field(file: &str) -> bool {
+    let field = match Field::load(Path::new(file)) {
+        Ok(field) => field,
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
     };
-    gen_near_switch();
+    println!("{}", field.name);
+    if !field.frame.is_empty() {
+        println!("{}", field.frame);
+    }
+    for element in field.elements() {
+        println!(
+            "  {}: ({:.4}, {:.4}) m facing {:.1} deg, {:.4} x {:.4} m{}{}",
+            element.name,
+            *(element.x() / si::M),
+            *(element.y() / si::M),
+            element.heading().degrees(),
+            *(element.width() / si::M),
+            *(element.length() / si::M),
+            if element.description.is_empty() {
+                ""
+            } else {
+                " - "
+            },
+            element.description
+        );
+    }
+    true
 }
 
-fn with_kappa(point: PointData, k: f64, dk: f64) -> MotionState<f64> {
-    MotionState {
-        // convert m to feet for the robot
-        x: *(point.x() / si::M) * 3.28084,
-        y: *(point.y() / si::M) * 3.28084,
-        t: point.rot(),
-        k,
-        dk,
+/// Loads the field, reporting why if that fails.
+fn load_field(file: &str) -> Option<Field> {
+    match Field::load(Path::new(file)) {
+        Ok(field) => Some(field),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
     }
 }
 
-use std::fs;
-fn export_pose(point: PointData, name: &str) {
-    fs::write(
-        format!("out/{}.java", name),
-        format!(
-            "public static Pose {} = new Pose({}, {}, {}, 0.0);",
-            name,
-            *(point.x() / si::M) * 3.28084,
-            *(point.y() / si::M) * 3.28084,
-            point.rot()
-        ),
-    )
-    .unwrap();
+/// Loads the robot profile, reporting why if that fails.
+fn load_robot(file: &str) -> Option<Robot> {
+    match Robot::load(Path::new(file)) {
+        Ok(robot) => Some(robot),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
 }
 
-fn zero_kappa(point: PointData) -> MotionState<f64> {
-    with_kappa(point, 0., 0.)
+/// Defines every auto on `field` for `robot`, those in code then those in the definition files
+/// in `paths`, reporting why if that fails.
+fn define_autos(field: &Field, robot: &Robot, paths: &Path) -> Option<autos::Autos> {
+    let define = || -> Result<autos::Autos, Box<dyn Error>> {
+        let mut autos = autos::define(field, robot)?;
+        let frames = autos::frames(field, robot)?;
+        for file in defs::files(paths)? {
+            for (name, chain) in defs::load(&file, &frames, robot)? {
+                if autos.paths.iter().any(|(defined, _)| *defined == name) {
+                    return Err(defs::DefError::Duplicate(name).into());
+                }
+                autos.paths.push((name, chain));
+            }
+        }
+        Ok(autos)
+    };
+    match define() {
+        Ok(autos) => Some(autos),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
 }
 
-fn basic_param(a: f64) -> EtaParam<f64> {
-    assert!(a > 0.0);
-    EtaParam::new(a, a, 0., 0., 0., 0.)
+/// Fails up front when the output directory is missing, and makes room for previews in it.
+fn prepare_output(options: &Options, out_dir: &Path) -> Result<(), ExportError> {
+    export::check_output_dir(out_dir)?;
+    if options.preview {
+        let dir = out_dir.join(preview::DIR);
+        fs::create_dir_all(&dir).map_err(|e| ExportError::io(&dir, e))?;
+    }
+    Ok(())
 }
 
-extern crate eta3_spline;
-use eta3_spline::*;
-extern crate csv;
-fn export_path(
-    points: Vec<MotionState<f64>>,
-    params: Vec<EtaParam<f64>>,
+/// Samples `chain`, checks how it fits the robot's limits and the field, then writes it with every
+/// exporter and as a preview if asked.
+fn write_path(
+    options: &Options,
     name: &str,
-    num_pts: usize,
-) {
-    let path = EtaCurve::new(points.as_slice(), params.as_slice()).unwrap();
-    let mut wtr = csv::WriterBuilder::new()
-        .has_headers(false)
-        .from_path(format!("out/{}.114path", name))
-        .unwrap();
-    wtr.write_record(&["x", "y", "distanceSoFar", "isEndPointInterpolation"])
-        .unwrap();
-    let mut t = 0.0;
-    let dt = 1.0 / num_pts as f64;
-    let mut dist = 0.0;
-
-    let mut last_point = path.eval(0.0);
-    while t < 1.0 {
-        let point = path.eval(t);
-
-        dist += f64::sqrt((point.0 - last_point.0).powi(2) + (point.1 - last_point.1).powi(2));
-        last_point = point;
-
-        wtr.serialize((point.0, point.1, dist, "False")).unwrap();
-        t += dt;
-    }
-    // end point interpolation
-    let last_state = points.get(points.len() - 1).unwrap();
-    let dx = last_state.t.cos();
-    let dy = last_state.t.sin();
-    // add approximately 6 feet of endpoint interpolation
-    const INTERP_DIST: f64 = 6.0;
-    let mut p = 0.0;
-    let dp = 0.1;
-
-    while p < INTERP_DIST {
-        wtr.serialize((
-            last_state.x + dx * p,
-            last_state.y + dy * p,
-            dist + p,
-            "True",
-        ))
-        .unwrap();
-        p += dp;
-    }
-
-    match wtr.serialize((dx, dy)) {
-        Err(e) => match e.into_kind() {
-            csv::ErrorKind::UnequalLengths { .. } => (),
-            _ => panic!(),
-        },
-        _ => (),
+    chain: &PathChain,
+    field: &Field,
+    out_dir: &Path,
+) -> PathOutcome {
+    let start = Instant::now();
+    let mut outcome = PathOutcome {
+        name: name.to_owned(),
+        points: 0,
+        report: None,
+        failures: Vec::new(),
+        elapsed: Default::default(),
+    };
+    let path = match chain.sample() {
+        Ok(path) => path,
+        Err(e) => {
+            outcome.failures.push(e);
+            outcome.elapsed = start.elapsed();
+            return outcome;
+        }
+    };
+    outcome.points = path.points.len();
+    let report = validate::validate(&path, &options.robot, field);
+    if options.strict && !report.is_ok() {
+        let violations = report.problems();
+        outcome
+            .failures
+            .push(ExportError::Infeasible { violations });
+    } else {
+        outcome.failures.extend(
+            options
+                .exporters
+                .iter()
+                .filter_map(|exporter| exporter.export_path(&path, name, out_dir).err()),
+        );
+        if options.preview {
+            let dir = out_dir.join(preview::DIR);
+            let written = preview::write(&path, name, field, &options.robot, &dir);
+            outcome.failures.extend(written.err());
+        }
+    }
+    outcome.report = Some(report);
+    outcome.elapsed = start.elapsed();
+    outcome
+}
+
+/// Writes the Java class listing every pose and the file each path was written to.
+fn write_java<'a>(
+    options: &Options,
+    poses: &[(String, PointData)],
+    paths: impl Iterator<Item = &'a String>,
+    out_dir: &Path,
+) -> Result<(), ExportError> {
+    // robot code loads paths in the first format asked for
+    let path_files: Vec<(String, String)> = paths
+        .map(|name| (name.clone(), options.exporters[0].path_file(name)))
+        .collect();
+    options.java.write(poses, &path_files, out_dir)
+}
+
+/// Expands every routine over the paths there are and writes the manifest of which files each
+/// runnable one drives.
+fn write_routines<'a>(
+    options: &Options,
+    paths: impl Iterator<Item = &'a String>,
+    out_dir: &Path,
+) -> Result<routines::Expansion, ExportError> {
+    let names: Vec<&String> = paths.collect();
+    let expansion = routines::expand_all(|leg| names.iter().any(|name| *name == leg));
+    let path_file = |name: &str| options.exporters[0].path_file(name);
+    routines::write(&expansion.entries, path_file, out_dir)?;
+    Ok(expansion)
+}
+
+/// Writes everything, carrying on past any pose or path that fails and reporting them all at the
+/// end. Returns whether everything was written.
+fn generate(options: Options) -> bool {
+    let out_dir = Path::new("out");
+    if let Err(e) = prepare_output(&options, out_dir) {
+        eprintln!("{}", e);
+        return false;
+    }
+
+    let field = match load_field(&options.field) {
+        Some(field) => field,
+        None => return false,
+    };
+    let mut autos = match define_autos(&field, &options.robot, &options.paths) {
+        Some(autos) => autos,
+        None => return false,
     };
+    if let Some(ref spline) = options.spline {
+        for (_, chain) in &mut autos.paths {
+            chain.set_spline(spline);
+        }
+    }
+    let mut failures = Vec::new();
+    for (name, pose) in &autos.poses {
+        for exporter in &options.exporters {
+            if let Err(e) = exporter.export_pose(*pose, name, out_dir) {
+                failures.push((name.clone(), e));
+            }
+        }
+    }
+
+    // paths don't depend on each other, so they are generated all at once and reported in order
+    let start = Instant::now();
+    let outcomes = batch::run(&autos.paths, options.jobs, |(name, chain)| {
+        write_path(&options, name, chain, &field, out_dir)
+    });
+    let wall = start.elapsed();
+    for outcome in &outcomes {
+        if let Some(ref report) = outcome.report {
+            println!("{}: {}", outcome.name, report);
+        }
+    }
+    batch::print_summary(&outcomes, options.jobs, wall);
+    for (outcome, (name, _)) in outcomes.into_iter().zip(&autos.paths) {
+        for e in outcome.failures {
+            failures.push((name.clone(), e));
+        }
+    }
+
+    let names = autos.paths.iter().map(|(name, _)| name);
+    if let Err(e) = write_java(&options, &autos.poses, names, out_dir) {
+        failures.push((options.java.class_name.clone(), e));
+    }
+    let names = autos.paths.iter().map(|(name, _)| name);
+    match write_routines(&options, names, out_dir) {
+        Ok(expansion) => {
+            routines::print_summary(&expansion);
+            // like paths past the robot's limits, routines missing legs only fail when strict
+            if options.strict {
+                for unrunnable in expansion.unrunnable {
+                    let name = unrunnable.name();
+                    let legs = unrunnable.missing;
+                    failures.push((name, ExportError::MissingLegs { legs }));
+                }
+            }
+        }
+        Err(e) => failures.push((routines::MANIFEST.to_owned(), e)),
+    }
+
+    if !failures.is_empty() {
+        eprintln!("{} outputs failed:", failures.len());
+        for (name, e) in &failures {
+            eprintln!("  {}: {}", name, e);
+        }
+    }
+    failures.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eta3_spline::MotionState;
+    use std::f64::consts::FRAC_PI_2;
+    use std::process;
+
+    #[test]
+    fn formats_keep_the_first_of_each() {
+        let args = ["--format", "java,114path,java,all"];
+        let options = options_from_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            options.formats,
+            ["java", "114path", "json", "binary", "wpilib", "wheels"]
+        );
+        assert_eq!(options.exporters.len(), options.formats.len());
+        assert_eq!(options.exporters[0].path_file("a"), "a.path.java");
+    }
+
+    #[test]
+    fn strict_fails_paths_past_the_limits() {
+        let options = |args: &[&str]| options_from_args(args.iter().map(|arg| arg.to_string()));
+        let field = Field::load(Path::new(field::DEFAULT_FIELD)).unwrap();
+        let state = |x, y, t| MotionState {
+            x,
+            y,
+            t,
+            k: 0.,
+            dk: 0.,
+        };
+        // a quarter turn in the middle of the field, tighter than the robot can take at speed
+        let chain = PathChain::new(path::Segment::forward(
+            vec![state(0., 10., FRAC_PI_2), state(1.5, 11.5, 0.)],
+            Spline::QuinticHermite,
+            100,
+        ));
+        let out_dir = std::env::temp_dir().join(format!("paths-2018-strict-{}", process::id()));
+        fs::create_dir_all(&out_dir).unwrap();
+
+        let loose = options(&["--format", "json"]);
+        let file = out_dir.join(loose.exporters[0].path_file("tight"));
+        let outcome = write_path(&loose, "tight", &chain, &field, &out_dir);
+        assert!(!outcome.report.unwrap().violations.is_empty());
+        assert!(outcome.failures.is_empty(), "{:?}", outcome.failures);
+        assert!(file.exists());
+        fs::remove_file(&file).unwrap();
+
+        let strict = options(&["--format", "json", "--strict"]);
+        let outcome = write_path(&strict, "tight", &chain, &field, &out_dir);
+        match outcome.failures[..] {
+            [ExportError::Infeasible { violations }] => assert!(violations > 0),
+            ref failures => panic!("{:?}", failures),
+        }
+        assert!(!file.exists());
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
 }
@@ -0,0 +1,269 @@
+use crate::geometry::{self, CurvatureProfile};
+use eta3_spline::*;
+
+/// Samples taken between each pair of waypoints when scoring a curve.
+const SAMPLES_PER_SPAN: usize = 60;
+/// The search stops once the simplex shrinks below this fraction of the parameter ranges.
+const TOLERANCE: f64 = 1e-3;
+const MAX_ITERATIONS: usize = 500;
+
+/// What the optimizer minimizes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Objective {
+    /// The tightest turn on the curve.
+    MaxCurvature,
+    /// The integral of the squared rate of change of curvature, for smooth turns.
+    CurvatureRate,
+    /// The length of the curve.
+    Length,
+}
+
+impl Objective {
+    pub const NAMES: &'static [&'static str] = &["max-curvature", "curvature-rate", "length"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "max-curvature" => Objective::MaxCurvature,
+            "curvature-rate" => Objective::CurvatureRate,
+            "length" => Objective::Length,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Objective::MaxCurvature => "max-curvature",
+            Objective::CurvatureRate => "curvature-rate",
+            Objective::Length => "length",
+        }
+    }
+
+    fn score(self, profile: &CurvatureProfile) -> f64 {
+        match self {
+            Objective::MaxCurvature => profile.max_curvature(),
+            Objective::CurvatureRate => profile.curvature_rate_energy(),
+            Objective::Length => profile.length(),
+        }
+    }
+}
+
+/// Constraints every candidate curve has to meet.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Constraints {
+    /// Upper bound on the curvature anywhere on the curve, in 1/ft.
+    pub max_curvature: Option<f64>,
+}
+
+/// The best parameters found for one curve.
+#[derive(Debug, Clone)]
+pub struct Optimized {
+    /// The six eta parameters for each span between waypoints.
+    pub params: Vec<[f64; 6]>,
+    pub score: f64,
+    /// How far the parameters are from meeting the constraints, zero when they do.
+    pub violation: f64,
+}
+
+/// Samples the curve through `states` with `params`, or `None` if the curve can't be built.
+pub fn profile(states: &[MotionState<f64>], params: &[EtaParam<f64>]) -> Option<CurvatureProfile> {
+    let curve = EtaCurve::new(states, params)?;
+    let n = SAMPLES_PER_SPAN * params.len();
+    let points: Vec<(f64, f64)> = (0..=n).map(|i| curve.eval(i as f64 / n as f64)).collect();
+    Some(geometry::curvature_profile(&points))
+}
+
+/// Scores the curve through `states` with the given parameters as they stand.
+pub fn score(
+    states: &[MotionState<f64>],
+    params: &[EtaParam<f64>],
+    objective: Objective,
+) -> Option<f64> {
+    profile(states, params).map(|p| objective.score(&p))
+}
+
+fn to_params(x: &[f64]) -> Vec<EtaParam<f64>> {
+    x.chunks(6)
+        .map(|e| EtaParam::new(e[0], e[1], e[2], e[3], e[4], e[5]))
+        .collect()
+}
+
+/// Searches for the eta parameters of the curve through `states` that minimize `objective`.
+///
+/// The first two parameters of each span scale the tangent at its ends, so they are kept
+/// positive and within a few chord lengths; the rest shape curvature and are bounded to a
+/// comparable range. The search is a Nelder-Mead simplex started from the straightest
+/// reasonable curve, so the same waypoints always give the same parameters.
+pub fn optimize(
+    states: &[MotionState<f64>],
+    objective: Objective,
+    constraints: Constraints,
+) -> Optimized {
+    let mut lower = Vec::new();
+    let mut upper = Vec::new();
+    let mut start = Vec::new();
+    for span in states.windows(2) {
+        let chord =
+            f64::sqrt((span[1].x - span[0].x).powi(2) + (span[1].y - span[0].y).powi(2)).max(1e-3);
+        lower.extend_from_slice(&[0.05 * chord, 0.05 * chord]);
+        upper.extend_from_slice(&[3. * chord, 3. * chord]);
+        lower.extend_from_slice(&[-5. * chord; 4]);
+        upper.extend_from_slice(&[5. * chord; 4]);
+        start.extend_from_slice(&[chord / 2., chord / 2., 0., 0., 0., 0.]);
+    }
+
+    // (violation, score), so candidates closer to meeting the constraints always win
+    let cost = |x: &[f64]| -> (f64, f64) {
+        match profile(states, &to_params(x)) {
+            Some(p) => {
+                let excess = constraints
+                    .max_curvature
+                    .map_or(0.0, |limit| (p.max_curvature() - limit).max(0.0));
+                (excess, objective.score(&p))
+            }
+            None => (f64::INFINITY, f64::INFINITY),
+        }
+    };
+    let (x, best) = nelder_mead(&start, &lower, &upper, cost);
+
+    Optimized {
+        params: x
+            .chunks(6)
+            .map(|e| [e[0], e[1], e[2], e[3], e[4], e[5]])
+            .collect(),
+        score: best.1,
+        violation: best.0,
+    }
+}
+
+/// Minimizes `cost` over the box between `lower` and `upper` with a Nelder-Mead simplex
+/// started at `start`, returning the best point and its cost. Costs are `(violation, score)`
+/// pairs compared in that order.
+fn nelder_mead<F>(start: &[f64], lower: &[f64], upper: &[f64], cost: F) -> (Vec<f64>, (f64, f64))
+where
+    F: Fn(&[f64]) -> (f64, f64),
+{
+    let better = |a: (f64, f64), b: (f64, f64)| a.0 < b.0 || (a.0 == b.0 && a.1 < b.1);
+
+    let clamp = |x: Vec<f64>| -> Vec<f64> {
+        x.iter()
+            .zip(lower.iter().zip(upper))
+            .map(|(v, (lo, hi))| v.max(*lo).min(*hi))
+            .collect()
+    };
+
+    // Nelder-Mead, starting from a simplex an eighth of the parameter ranges across
+    let n = start.len();
+    let mut simplex: Vec<(Vec<f64>, (f64, f64))> = vec![(start.to_vec(), cost(start))];
+    for i in 0..n {
+        let mut vertex = start.to_vec();
+        vertex[i] += (upper[i] - lower[i]) / 8.;
+        let vertex = clamp(vertex);
+        let c = cost(&vertex);
+        simplex.push((vertex, c));
+    }
+    let order = |a: &(Vec<f64>, (f64, f64)), b: &(Vec<f64>, (f64, f64))| {
+        if better(a.1, b.1) {
+            std::cmp::Ordering::Less
+        } else if better(b.1, a.1) {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    };
+    let size = |simplex: &[(Vec<f64>, (f64, f64))]| {
+        (0..n)
+            .map(|i| {
+                let range = upper[i] - lower[i];
+                simplex
+                    .iter()
+                    .map(|v| (v.0[i] - simplex[0].0[i]).abs() / range)
+                    .fold(0.0, f64::max)
+            })
+            .fold(0.0, f64::max)
+    };
+    for _ in 0..MAX_ITERATIONS {
+        simplex.sort_by(order);
+        if size(&simplex) < TOLERANCE {
+            break;
+        }
+        let worst = simplex[n].clone();
+        let centroid: Vec<f64> = (0..n)
+            .map(|i| simplex[..n].iter().map(|v| v.0[i]).sum::<f64>() / n as f64)
+            .collect();
+        let toward = |t: f64| -> Vec<f64> {
+            clamp(
+                centroid
+                    .iter()
+                    .zip(&worst.0)
+                    .map(|(c, w)| c + t * (c - w))
+                    .collect(),
+            )
+        };
+
+        let reflected = toward(1.);
+        let r = cost(&reflected);
+        if better(r, simplex[0].1) {
+            let expanded = toward(2.);
+            let e = cost(&expanded);
+            simplex[n] = if better(e, r) {
+                (expanded, e)
+            } else {
+                (reflected, r)
+            };
+        } else if better(r, simplex[n - 1].1) {
+            simplex[n] = (reflected, r);
+        } else {
+            let contracted = toward(-0.5);
+            let c = cost(&contracted);
+            if better(c, worst.1) {
+                simplex[n] = (contracted, c);
+            } else {
+                // shrink everything toward the best vertex
+                let best = simplex[0].0.clone();
+                for vertex in &mut simplex[1..] {
+                    let shrunk: Vec<f64> = best
+                        .iter()
+                        .zip(&vertex.0)
+                        .map(|(b, v)| b + 0.5 * (v - b))
+                        .collect();
+                    let c = cost(&shrunk);
+                    *vertex = (shrunk, c);
+                }
+            }
+        }
+    }
+    simplex.sort_by(order);
+    simplex.swap_remove(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_bottom_of_a_quadratic() {
+        let cost = |x: &[f64]| (0.0, (x[0] - 1.).powi(2) + 2. * (x[1] + 0.5).powi(2) + 3.);
+        let (x, best) = nelder_mead(&[-3., 4.], &[-5., -5.], &[5., 5.], cost);
+        assert!((x[0] - 1.).abs() < 0.02, "{:?}", x);
+        assert!((x[1] + 0.5).abs() < 0.02, "{:?}", x);
+        assert!((best.1 - 3.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stays_in_bounds() {
+        // the unconstrained minimum is outside the box, so the best is on its edge
+        let cost = |x: &[f64]| (0.0, (x[0] - 8.).powi(2) + x[1].powi(2));
+        let (x, _) = nelder_mead(&[0., 1.], &[-5., -5.], &[5., 5.], cost);
+        assert!((x[0] - 5.).abs() < 0.02, "{:?}", x);
+        assert!(x[1].abs() < 0.02, "{:?}", x);
+    }
+
+    #[test]
+    fn meeting_constraints_comes_first() {
+        // the score is lowest at 0, but only x >= 2 meets the constraint
+        let cost = |x: &[f64]| ((2. - x[0]).max(0.), x[0].powi(2));
+        let (x, best) = nelder_mead(&[-4.], &[-5.], &[5.], cost);
+        assert_eq!(best.0, 0.);
+        assert!((x[0] - 2.).abs() < 0.02, "{:?}", x);
+    }
+}
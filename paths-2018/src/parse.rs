@@ -0,0 +1,464 @@
+use crate::export::RobotPose;
+use crate::path::{PathMarker, PathPoint, SampledPath};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+const COLUMNS: &[&str] = &[
+    "x",
+    "y",
+    "distanceSoFar",
+    "isEndPointInterpolation",
+    "isReversed",
+];
+/// Files written before reverse driving existed lack the `isReversed` column.
+const LEGACY_COLUMNS: usize = 4;
+
+/// Why a `.114path`, `.markers.csv` or pose file could not be read. Lines are 1-based.
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    Csv(csv::Error),
+    MissingHeader,
+    UnexpectedHeader {
+        found: Vec<String>,
+    },
+    FieldCount {
+        line: u64,
+        expected: usize,
+        found: usize,
+    },
+    InvalidNumber {
+        line: u64,
+        column: &'static str,
+        value: String,
+    },
+    InvalidFlag {
+        line: u64,
+        column: &'static str,
+        value: String,
+    },
+    /// The trailing `(dx, dy)` end direction row is missing.
+    MissingFooter,
+    /// Rows after the end direction footer.
+    TrailingRows {
+        line: u64,
+    },
+    NoPoints,
+    /// The sidecar next to a path could not be read.
+    Markers(Box<ParseError>),
+    Json(serde_json::Error),
+    /// A file that isn't a pose in any format a pose is exported in.
+    NotAPose,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "{}", e),
+            ParseError::Csv(e) => write!(f, "{}", e),
+            ParseError::MissingHeader => write!(f, "missing header row"),
+            ParseError::UnexpectedHeader { found } => write!(
+                f,
+                "expected header {}, found {}",
+                COLUMNS.join(","),
+                found.join(",")
+            ),
+            ParseError::FieldCount {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} fields, found {}",
+                line, expected, found
+            ),
+            ParseError::InvalidNumber {
+                line,
+                column,
+                value,
+            } => write!(f, "line {}: {} is not a number: {:?}", line, column, value),
+            ParseError::InvalidFlag {
+                line,
+                column,
+                value,
+            } => write!(
+                f,
+                "line {}: {} is not True or False: {:?}",
+                line, column, value
+            ),
+            ParseError::MissingFooter => write!(f, "missing end direction row"),
+            ParseError::TrailingRows { line } => {
+                write!(f, "line {}: rows after the end direction row", line)
+            }
+            ParseError::NoPoints => write!(f, "path has no points"),
+            ParseError::Markers(e) => write!(f, "markers: {}", e),
+            ParseError::Json(e) => write!(f, "{}", e),
+            ParseError::NotAPose => write!(f, "not a pose"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+impl From<csv::Error> for ParseError {
+    fn from(e: csv::Error) -> Self {
+        ParseError::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(e: serde_json::Error) -> Self {
+        ParseError::Json(e)
+    }
+}
+
+fn number(record: &csv::StringRecord, line: u64, i: usize) -> Result<f64, ParseError> {
+    record[i]
+        .trim()
+        .parse()
+        .map_err(|_| ParseError::InvalidNumber {
+            line,
+            column: COLUMNS[i],
+            value: record[i].to_owned(),
+        })
+}
+
+fn flag(record: &csv::StringRecord, line: u64, i: usize) -> Result<bool, ParseError> {
+    match record[i].trim() {
+        "True" | "true" => Ok(true),
+        "False" | "false" => Ok(false),
+        value => Err(ParseError::InvalidFlag {
+            line,
+            column: COLUMNS[i],
+            value: value.to_owned(),
+        }),
+    }
+}
+
+/// Parses the contents of a `.114path` file. Markers live in a separate file, so the result has
+/// none.
+pub fn parse_114path<R: io::Read>(reader: R) -> Result<SampledPath, ParseError> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let mut records = rdr.records();
+
+    let header = records.next().ok_or(ParseError::MissingHeader)??;
+    let columns = header.len();
+    if (columns != COLUMNS.len() && columns != LEGACY_COLUMNS)
+        || header
+            .iter()
+            .zip(COLUMNS)
+            .any(|(found, want)| found != *want)
+    {
+        return Err(ParseError::UnexpectedHeader {
+            found: header.iter().map(str::to_owned).collect(),
+        });
+    }
+
+    let mut points = Vec::new();
+    let mut end_direction = None;
+    for record in records {
+        let record = record?;
+        let line = record.position().map_or(0, |p| p.line());
+        if end_direction.is_some() {
+            return Err(ParseError::TrailingRows { line });
+        }
+        if record.len() == 2 {
+            let dx = number(&record, line, 0)?;
+            let dy = number(&record, line, 1)?;
+            end_direction = Some((dx, dy));
+            continue;
+        }
+        if record.len() != columns {
+            return Err(ParseError::FieldCount {
+                line,
+                expected: columns,
+                found: record.len(),
+            });
+        }
+        points.push(PathPoint {
+            x: number(&record, line, 0)?,
+            y: number(&record, line, 1)?,
+            distance: number(&record, line, 2)?,
+            interpolated: flag(&record, line, 3)?,
+            reversed: columns > LEGACY_COLUMNS && flag(&record, line, 4)?,
+        });
+    }
+
+    if points.is_empty() {
+        return Err(ParseError::NoPoints);
+    }
+    Ok(SampledPath {
+        points,
+        end_direction: end_direction.ok_or(ParseError::MissingFooter)?,
+        markers: Vec::new(),
+    })
+}
+
+/// Parses the contents of a `.markers.csv` sidecar.
+pub fn parse_markers<R: io::Read>(reader: R) -> Result<Vec<PathMarker>, ParseError> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let mut records = rdr.records();
+
+    let header = records.next().ok_or(ParseError::MissingHeader)??;
+    if header.iter().ne(["name", "distanceSoFar"].iter().cloned()) {
+        return Err(ParseError::UnexpectedHeader {
+            found: header.iter().map(str::to_owned).collect(),
+        });
+    }
+    let mut markers = Vec::new();
+    for record in records {
+        let record = record?;
+        let line = record.position().map_or(0, |p| p.line());
+        if record.len() != 2 {
+            return Err(ParseError::FieldCount {
+                line,
+                expected: 2,
+                found: record.len(),
+            });
+        }
+        markers.push(PathMarker {
+            name: record[0].to_owned(),
+            distance: record[1]
+                .trim()
+                .parse()
+                .map_err(|_| ParseError::InvalidNumber {
+                    line,
+                    column: "distanceSoFar",
+                    value: record[1].to_owned(),
+                })?,
+        });
+    }
+    Ok(markers)
+}
+
+/// Reads a `.114path` file, along with the markers in its `.markers.csv` sidecar if there is one.
+pub fn read_114path(path: &Path) -> Result<SampledPath, ParseError> {
+    let mut parsed = parse_114path(File::open(path)?)?;
+    let sidecar = path.with_extension("markers.csv");
+    if sidecar.exists() {
+        parsed.markers = File::open(&sidecar)
+            .map_err(ParseError::from)
+            .and_then(parse_markers)
+            .map_err(|e| ParseError::Markers(Box::new(e)))?;
+    }
+    Ok(parsed)
+}
+
+/// Reads a pose back from whichever format it was exported in: a `.pose.json`, a `.114pose`, or
+/// the Java declaration in a `.pose.java`.
+pub fn read_pose(path: &Path) -> Result<RobotPose, ParseError> {
+    let bytes = fs::read(path)?;
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if name.ends_with(".pose.json") {
+        Ok(serde_json::from_slice(&bytes)?)
+    } else if name.ends_with(".114pose") {
+        parse_binary_pose(&bytes)
+    } else {
+        parse_java_pose(&String::from_utf8_lossy(&bytes))
+    }
+}
+
+fn parse_binary_pose(bytes: &[u8]) -> Result<RobotPose, ParseError> {
+    if bytes.len() != 28 || &bytes[..4] != b"114O" {
+        return Err(ParseError::NotAPose);
+    }
+    let value = |i: usize| {
+        let mut le = [0; 8];
+        le.copy_from_slice(&bytes[4 + 8 * i..12 + 8 * i]);
+        f64::from_le_bytes(le)
+    };
+    Ok(RobotPose {
+        x: value(0),
+        y: value(1),
+        heading: value(2),
+    })
+}
+
+/// Parses `public static Pose <name> = new Pose(x, y, heading, 0.0);`.
+fn parse_java_pose(source: &str) -> Result<RobotPose, ParseError> {
+    let args = source
+        .trim()
+        .strip_prefix("public static Pose ")
+        .and_then(|rest| rest.split("new Pose(").nth(1))
+        .and_then(|rest| rest.strip_suffix(");"))
+        .ok_or(ParseError::NotAPose)?;
+    let values = args
+        .split(',')
+        .map(|v| v.trim().parse())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| ParseError::NotAPose)?;
+    match values[..] {
+        [x, y, heading, _] => Ok(RobotPose { x, y, heading }),
+        _ => Err(ParseError::NotAPose),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{Exporter, Path114};
+
+    fn sample() -> SampledPath {
+        let point = |x, distance, interpolated, reversed| PathPoint {
+            x,
+            y: 0.5,
+            distance,
+            interpolated,
+            reversed,
+        };
+        SampledPath {
+            points: vec![
+                point(0.0, 0.0, false, false),
+                point(1.25, 1.25, false, true),
+                point(1.35, 1.35, true, true),
+            ],
+            end_direction: (-1.0, 0.0),
+            markers: vec![PathMarker {
+                name: "eject".to_owned(),
+                distance: 0.75,
+            }],
+        }
+    }
+
+    #[test]
+    fn round_trips_path114() {
+        let dir = std::env::temp_dir().join(format!("paths-2018-parse-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = sample();
+        Path114.export_path(&path, "roundTrip", &dir).unwrap();
+        let file = dir.join(Path114.path_file("roundTrip"));
+        let read = read_114path(&file);
+        // a broken sidecar fails the path it belongs to
+        fs::write(
+            file.with_extension("markers.csv"),
+            "name,distanceSoFar\neject,\n",
+        )
+        .unwrap();
+        let broken = read_114path(&file);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(read.unwrap(), path);
+        assert!(matches!(broken, Err(ParseError::Markers(_))));
+    }
+
+    #[test]
+    fn reads_legacy_columns_as_forward() {
+        let file = "x,y,distanceSoFar,isEndPointInterpolation\n\
+                    0,0,0,False\n\
+                    1,0,1,True\n\
+                    1,0\n";
+        let path = parse_114path(file.as_bytes()).unwrap();
+        assert_eq!(path.points.len(), 2);
+        assert!(path.points.iter().all(|p| !p.reversed));
+        assert!(path.points[1].interpolated);
+        assert_eq!(path.end_direction, (1.0, 0.0));
+    }
+
+    #[test]
+    fn missing_footer() {
+        let file = "x,y,distanceSoFar,isEndPointInterpolation,isReversed\n\
+                    0,0,0,False,False\n";
+        match parse_114path(file.as_bytes()) {
+            Err(ParseError::MissingFooter) => (),
+            other => panic!("expected a missing footer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn trailing_rows_give_their_line() {
+        let file = "x,y,distanceSoFar,isEndPointInterpolation,isReversed\n\
+                    0,0,0,False,False\n\
+                    1,0\n\
+                    2,0,2,False,False\n";
+        match parse_114path(file.as_bytes()) {
+            Err(ParseError::TrailingRows { line: 4 }) => (),
+            other => panic!("expected trailing rows on line 4, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bad_flag_and_number() {
+        let header = "x,y,distanceSoFar,isEndPointInterpolation,isReversed\n";
+        let flag = format!("{}0,0,0,False,yes\n1,0\n", header);
+        match parse_114path(flag.as_bytes()) {
+            Err(ParseError::InvalidFlag {
+                line: 2,
+                column: "isReversed",
+                ref value,
+            }) if value == "yes" => (),
+            other => panic!("expected a bad flag, got {:?}", other),
+        }
+        let number = format!("{}0,0,0,False,False\n1,zero,1,False,False\n1,0\n", header);
+        match parse_114path(number.as_bytes()) {
+            Err(ParseError::InvalidNumber {
+                line: 3,
+                column: "y",
+                ref value,
+            }) if value == "zero" => (),
+            other => panic!("expected a bad number, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_markers() {
+        match parse_markers("marker,distance\n".as_bytes()) {
+            Err(ParseError::UnexpectedHeader { .. }) => (),
+            other => panic!("expected an unexpected header, got {:?}", other),
+        }
+        match parse_markers("name,distanceSoFar\neject\n".as_bytes()) {
+            Err(ParseError::FieldCount {
+                line: 2,
+                expected: 2,
+                found: 1,
+            }) => (),
+            other => panic!("expected a short row, got {:?}", other),
+        }
+        match parse_markers("name,distanceSoFar\neject,far\n".as_bytes()) {
+            Err(ParseError::InvalidNumber { line: 2, .. }) => (),
+            other => panic!("expected a bad distance, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bad_poses() {
+        let java = "public static Pose start = new Pose(1.0, 2.0, 0.5, 0.0);";
+        let pose = parse_java_pose(java).unwrap();
+        assert_eq!((pose.x, pose.y, pose.heading), (1.0, 2.0, 0.5));
+        for source in &[
+            "public static final double[][] start = {",
+            "public static Pose start = new Pose(1.0, 2.0, 0.5);",
+            "public static Pose start = new Pose(1.0, two, 0.5, 0.0);",
+        ] {
+            assert!(matches!(parse_java_pose(source), Err(ParseError::NotAPose)));
+        }
+
+        let mut binary = b"114O".to_vec();
+        for value in &[1.0f64, 2.0, 0.5] {
+            binary.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(parse_binary_pose(&binary).unwrap().y, 2.0);
+        assert!(matches!(
+            parse_binary_pose(&binary[..20]),
+            Err(ParseError::NotAPose)
+        ));
+        binary[0] = b'X';
+        assert!(matches!(
+            parse_binary_pose(&binary),
+            Err(ParseError::NotAPose)
+        ));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::curve::Spline;
+use crate::error::ExportError;
+use crate::FEET_PER_METER;
+use coord_frames::Meter;
+use dim::si;
+use eta3_spline::*;
+use serde::Serialize;
+use std::f64::consts::PI;
+
+/// Distance past the final waypoint to keep emitting points along the final heading, in feet.
+const INTERP_DIST: f64 = 6.0;
+const INTERP_STEP: f64 = 0.1;
+
+/// One piece of a path that is driven in a single direction.
+///
+/// Waypoint headings are always the direction the robot is facing, so a reversed segment that
+/// backs away from a target keeps the target's heading on its first waypoint.
+pub struct Segment {
+    points: Vec<MotionState<f64>>,
+    spline: Spline,
+    reversed: bool,
+    num_pts: usize,
+}
+
+impl Segment {
+    /// `spline` is usually the eta3 parameters for each span.
+    pub fn forward(
+        points: Vec<MotionState<f64>>,
+        spline: impl Into<Spline>,
+        num_pts: usize,
+    ) -> Self {
+        Self {
+            points,
+            spline: spline.into(),
+            reversed: false,
+            num_pts,
+        }
+    }
+
+    pub fn reverse(
+        points: Vec<MotionState<f64>>,
+        spline: impl Into<Spline>,
+        num_pts: usize,
+    ) -> Self {
+        Self {
+            reversed: true,
+            ..Self::forward(points, spline, num_pts)
+        }
+    }
+
+    pub fn spline(&self) -> &Spline {
+        &self.spline
+    }
+
+    /// The waypoints as seen along the direction of travel, which is what the curve is built from.
+    pub fn travel_states(&self) -> Vec<MotionState<f64>> {
+        // driving backwards flips the tangent, and with it the sign of the curvature
+        let (flip, sign) = if self.reversed {
+            (PI, -1.0)
+        } else {
+            (0.0, 1.0)
+        };
+        self.points
+            .iter()
+            .map(|s| MotionState {
+                x: s.x,
+                y: s.y,
+                t: s.t + flip,
+                k: s.k * sign,
+                dk: s.dk,
+            })
+            .collect()
+    }
+}
+
+/// Where along a path a marker is placed.
+#[derive(Debug, Copy, Clone)]
+pub enum MarkerPosition {
+    /// Distance traveled from the start of the path.
+    Distance(Meter),
+    /// Distance remaining before the final waypoint, e.g. "1 m before the scale".
+    DistanceFromEnd(Meter),
+    /// Fraction of the total path length, from 0 to 1.
+    Fraction(f64),
+    /// The point where the path passes a waypoint, indexed within its segment.
+    Waypoint { segment: usize, index: usize },
+}
+
+/// A named event that subsystems trigger on once the robot has driven past it.
+#[derive(Debug, Clone)]
+pub struct Marker {
+    name: String,
+    position: MarkerPosition,
+}
+
+/// A sequence of segments driven back to back, e.g. drive to the scale, back up, drive to a cube.
+pub struct PathChain {
+    segments: Vec<Segment>,
+    markers: Vec<Marker>,
+}
+
+impl PathChain {
+    pub fn new(first: Segment) -> Self {
+        Self {
+            segments: vec![first],
+            markers: Vec::new(),
+        }
+    }
+
+    pub fn then(mut self, next: Segment) -> Self {
+        self.segments.push(next);
+        self
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Builds every segment from `spline` instead, to compare curve families on the same
+    /// waypoints.
+    pub fn set_spline(&mut self, spline: &Spline) {
+        for segment in &mut self.segments {
+            segment.spline = spline.clone();
+        }
+    }
+
+    pub fn with_marker(mut self, name: &str, position: MarkerPosition) -> Self {
+        self.markers.push(Marker {
+            name: name.to_owned(),
+            position,
+        });
+        self
+    }
+
+    /// Samples every segment in order, accumulating distance across segment boundaries, then
+    /// appends the end point interpolation along the final direction of travel.
+    pub fn sample(&self) -> Result<SampledPath, ExportError> {
+        let mut points = Vec::new();
+        let mut dist = 0.0;
+        let mut last_point = None;
+        let mut last_state = None;
+        // index of the first sample of each segment, plus one past the last
+        let mut bounds = vec![0];
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            let states = segment.travel_states();
+            let path = segment
+                .spline
+                .curve(&states)
+                .ok_or(ExportError::InvalidSpline { segment: i })?;
+
+            let mut t = 0.0;
+            let dt = 1.0 / segment.num_pts as f64;
+            let mut last = last_point.unwrap_or_else(|| path.eval(0.0));
+            while t < 1.0 {
+                let point = path.eval(t);
+
+                dist += f64::sqrt((point.0 - last.0).powi(2) + (point.1 - last.1).powi(2));
+                last = point;
+
+                points.push(PathPoint {
+                    x: point.0,
+                    y: point.1,
+                    distance: dist,
+                    interpolated: false,
+                    reversed: segment.reversed,
+                });
+                t += dt;
+            }
+            last_point = Some(last);
+            last_state = states.into_iter().last().map(|s| (s, segment.reversed));
+            bounds.push(points.len());
+        }
+
+        let mut markers = self
+            .markers
+            .iter()
+            .map(|marker| {
+                Ok(PathMarker {
+                    name: marker.name.clone(),
+                    distance: self.resolve(marker, &points, &bounds, dist)?,
+                })
+            })
+            .collect::<Result<Vec<_>, ExportError>>()?;
+        markers.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
+
+        // end point interpolation
+        let (last_state, reversed) = last_state.expect("a path chain has at least one segment");
+        let dx = last_state.t.cos();
+        let dy = last_state.t.sin();
+        let mut p = 0.0;
+        while p < INTERP_DIST {
+            points.push(PathPoint {
+                x: last_state.x + dx * p,
+                y: last_state.y + dy * p,
+                distance: dist + p,
+                interpolated: true,
+                reversed,
+            });
+            p += INTERP_STEP;
+        }
+
+        Ok(SampledPath {
+            points,
+            end_direction: (dx, dy),
+            markers,
+        })
+    }
+
+    /// Finds the distance along the sampled path at which a marker fires.
+    fn resolve(
+        &self,
+        marker: &Marker,
+        points: &[PathPoint],
+        bounds: &[usize],
+        total: f64,
+    ) -> Result<f64, ExportError> {
+        let feet = |m: Meter| *(m / si::M) * FEET_PER_METER;
+        let dist = match marker.position {
+            MarkerPosition::Distance(m) => feet(m),
+            MarkerPosition::DistanceFromEnd(m) => total - feet(m),
+            MarkerPosition::Fraction(f) => total * f,
+            MarkerPosition::Waypoint { segment, index } => {
+                let waypoint = self
+                    .segments
+                    .get(segment)
+                    .and_then(|s| s.points.get(index))
+                    .ok_or_else(|| ExportError::MissingWaypoint {
+                        marker: marker.name.clone(),
+                        segment,
+                        index,
+                    })?;
+                // the sample in that segment that comes closest to the waypoint
+                points[bounds[segment]..bounds[segment + 1]]
+                    .iter()
+                    .map(|p| ((p.x - waypoint.x).powi(2) + (p.y - waypoint.y).powi(2), p))
+                    .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                    .map(|(_, p)| p.distance)
+                    .unwrap_or(total)
+            }
+        };
+        Ok(dist.max(0.0).min(total))
+    }
+}
+
+/// A single sample along a path, in feet.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathPoint {
+    pub x: f64,
+    pub y: f64,
+    pub distance: f64,
+    /// Whether this point lies on the straight line extending past the end of the path.
+    pub interpolated: bool,
+    /// Whether the robot is driving backwards through this point.
+    pub reversed: bool,
+}
+
+/// A path as it is written out: every sample plus the direction of travel at the end.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampledPath {
+    pub points: Vec<PathPoint>,
+    pub end_direction: (f64, f64),
+    /// Sorted by distance.
+    pub markers: Vec<PathMarker>,
+}
+
+/// A marker resolved to the distance along the path it fires at, in feet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathMarker {
+    pub name: String,
+    pub distance: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(x: f64, y: f64, t: f64) -> MotionState<f64> {
+        MotionState {
+            x,
+            y,
+            t,
+            k: 0.,
+            dk: 0.,
+        }
+    }
+
+    /// Drives 10 ft along `x`, then backs 5 ft the way it came, facing `x` throughout.
+    fn there_and_back() -> PathChain {
+        PathChain::new(Segment::forward(
+            vec![state(0., 0., 0.), state(10., 0., 0.)],
+            Spline::QuinticHermite,
+            100,
+        ))
+        .then(Segment::reverse(
+            vec![state(10., 0., 0.), state(5., 0., 0.)],
+            Spline::QuinticHermite,
+            50,
+        ))
+    }
+
+    #[test]
+    fn reversing_flips_heading_and_curvature() {
+        let points = vec![
+            MotionState {
+                k: 0.5,
+                ..state(0., 0., 0.25)
+            },
+            state(1., 0., 0.),
+        ];
+        let forward = Segment::forward(points.clone(), Spline::QuinticHermite, 10);
+        let reverse = Segment::reverse(points, Spline::QuinticHermite, 10);
+        assert_eq!(forward.travel_states()[0].t, 0.25);
+        assert_eq!(forward.travel_states()[0].k, 0.5);
+        assert_eq!(reverse.travel_states()[0].t, 0.25 + PI);
+        assert_eq!(reverse.travel_states()[0].k, -0.5);
+    }
+
+    #[test]
+    fn distance_accumulates_across_segments() {
+        let path = there_and_back().sample().unwrap();
+        assert!(path
+            .points
+            .windows(2)
+            .all(|w| w[1].distance >= w[0].distance));
+
+        let driven: Vec<&PathPoint> = path.points.iter().filter(|p| !p.interpolated).collect();
+        assert_eq!(driven.len(), 150);
+        assert!(driven[..100].iter().all(|p| !p.reversed));
+        assert!(driven[100..].iter().all(|p| p.reversed));
+        // the reversed segment starts where the forward one ended, 10 ft in
+        assert!((driven[100].distance - 10.).abs() < 1e-9);
+        assert!((driven[100].x - 10.).abs() < 1e-9);
+
+        // interpolation carries on backing up, along the direction of travel
+        let extra: Vec<&PathPoint> = path.points.iter().filter(|p| p.interpolated).collect();
+        assert!(extra.iter().all(|p| p.reversed));
+        assert_eq!(path.end_direction.0, -1.);
+        assert!(path.end_direction.1.abs() < 1e-12);
+        assert!((extra[0].distance - 15.).abs() < 0.2);
+        assert!(extra[1].x < extra[0].x);
+    }
+
+    #[test]
+    fn markers_resolve_to_distances() {
+        let path = there_and_back()
+            .with_marker("end", MarkerPosition::DistanceFromEnd(1. * si::M))
+            .with_marker("half", MarkerPosition::Fraction(0.5))
+            .with_marker("meter", MarkerPosition::Distance(1. * si::M))
+            .with_marker(
+                "turnaround",
+                MarkerPosition::Waypoint {
+                    segment: 1,
+                    index: 0,
+                },
+            )
+            .with_marker("past", MarkerPosition::Distance(100. * si::M))
+            .sample()
+            .unwrap();
+        let total = path
+            .points
+            .iter()
+            .rfind(|p| !p.interpolated)
+            .unwrap()
+            .distance;
+
+        let names: Vec<&str> = path.markers.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["meter", "half", "turnaround", "end", "past"]);
+        let at = |name: &str| {
+            path.markers
+                .iter()
+                .find(|m| m.name == name)
+                .unwrap()
+                .distance
+        };
+        assert!((at("meter") - FEET_PER_METER).abs() < 1e-9);
+        assert!((at("half") - total / 2.).abs() < 1e-9);
+        assert!((at("turnaround") - 10.).abs() < 1e-9);
+        assert!((at("end") - (total - FEET_PER_METER)).abs() < 1e-9);
+        // markers past the end fire at the end
+        assert_eq!(at("past"), total);
+    }
+
+    #[test]
+    fn marker_at_missing_waypoint() {
+        let chain = there_and_back().with_marker(
+            "nowhere",
+            MarkerPosition::Waypoint {
+                segment: 1,
+                index: 2,
+            },
+        );
+        match chain.sample() {
+            Err(ExportError::MissingWaypoint {
+                segment: 1,
+                index: 2,
+                ..
+            }) => (),
+            Err(e) => panic!("expected a missing waypoint, got {}", e),
+            Ok(_) => panic!("expected a missing waypoint"),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! SVG drawings of generated paths over the field, laid out like `visualize.py` draws them:
+//! downfield to the right and the right side of the field toward the bottom, in feet.
+
+use crate::error::ExportError;
+use crate::field::Field;
+use crate::path::{PathPoint, SampledPath};
+use crate::robot::Robot;
+use crate::FEET_PER_METER;
+use dim::si;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Where previews go, inside the output directory.
+pub const DIR: &str = "preview";
+/// Draw the robot's outline at every this many driven points.
+const OUTLINE_EVERY: usize = 25;
+/// The picture of the field `visualize.py` draws on, relative to a preview.
+const FIELD_IMAGE: &str = "../../fieldCropped.png";
+
+/// `text` made safe to put in HTML or SVG, as content or a quoted attribute. Names and messages
+/// come from files and must not be able to add markup.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            '\'' => out += "&#39;",
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn feet(m: dim::si::Meter<f64>) -> f64 {
+    *(m / si::M) * FEET_PER_METER
+}
+
+/// The corners of a `length` by `width` rectangle centered on `(x, y)` with its length along
+/// `heading`, in order around it.
+pub fn corners(x: f64, y: f64, heading: f64, length: f64, width: f64) -> [(f64, f64); 4] {
+    let (c, s) = (heading.cos(), heading.sin());
+    let corner = |l: f64, w: f64| {
+        (
+            x + l * length / 2. * c - w * width / 2. * s,
+            y + l * length / 2. * s + w * width / 2. * c,
+        )
+    };
+    [
+        corner(1., 1.),
+        corner(1., -1.),
+        corner(-1., -1.),
+        corner(-1., 1.),
+    ]
+}
+
+/// The rectangle as SVG points.
+fn rectangle(x: f64, y: f64, heading: f64, length: f64, width: f64) -> String {
+    corners(x, y, heading, length, width)
+        .iter()
+        // the drawing's x is downfield, its y across the field
+        .map(|(px, py)| format!("{:.3},{:.3}", py, px))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The part of the field to draw, as the downfield distance of its left edge, the field x of its
+/// top edge, and its size, in feet. That's the carpet, or failing that wherever `points` go.
+pub fn extent(field: &Field, points: &[PathPoint]) -> (f64, f64, f64, f64) {
+    match field.element("field") {
+        Ok(carpet) => (
+            feet(carpet.y()),
+            feet(carpet.x() - carpet.width() / 2.),
+            feet(carpet.length()),
+            feet(carpet.width()),
+        ),
+        Err(_) => {
+            let xs = points.iter().map(|p| p.x);
+            let ys = points.iter().map(|p| p.y);
+            let (x0, x1) = (xs.clone().fold(0.0, f64::min), xs.fold(0.0, f64::max));
+            let (y0, y1) = (ys.clone().fold(0.0, f64::min), ys.fold(0.0, f64::max));
+            (y0 - 3., x0 - 3., y1 - y0 + 6., x1 - x0 + 6.)
+        }
+    }
+}
+
+/// Draws `path` on `field` with `robot`'s outline along it.
+pub fn render(path: &SampledPath, name: &str, field: &Field, robot: &Robot) -> String {
+    render_over(path, name, field, robot, FIELD_IMAGE, "")
+}
+
+/// Draws `path` like `render`, but on the field picture at `image` and with `overlay`, more SVG
+/// in the same coordinates, drawn on top.
+pub fn render_over(
+    path: &SampledPath,
+    name: &str,
+    field: &Field,
+    robot: &Robot,
+    image: &str,
+    overlay: &str,
+) -> String {
+    let (left, top, width, height) = extent(field, &path.points);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{:.3} {:.3} {:.3} {:.3}\" \
+         width=\"{:.0}\" height=\"{:.0}\">",
+        left,
+        top,
+        width,
+        height,
+        width * 20.,
+        height * 20.
+    );
+    let _ = writeln!(svg, "<title>{}</title>", escape(name));
+    let _ = writeln!(
+        svg,
+        "<image href=\"{}\" x=\"{:.3}\" y=\"{:.3}\" width=\"{:.3}\" height=\"{:.3}\" \
+         preserveAspectRatio=\"none\"/>",
+        escape(image),
+        left,
+        top,
+        width,
+        height
+    );
+    let _ = writeln!(
+        svg,
+        "<line x1=\"{:.3}\" y1=\"0\" x2=\"{:.3}\" y2=\"0\" stroke=\"black\" stroke-width=\"0.05\"/>",
+        left,
+        left + width
+    );
+
+    for element in field.elements() {
+        if element.name == "field" {
+            continue;
+        }
+        let (x, y) = (feet(element.x()), feet(element.y()));
+        let (length, size) = (feet(element.length()), feet(element.width()));
+        if length > 0.0 && size > 0.0 {
+            let _ = writeln!(
+                svg,
+                "<polygon points=\"{}\" fill=\"none\" stroke=\"gray\" stroke-width=\"0.08\"/>",
+                rectangle(x, y, element.heading().radians(), length, size)
+            );
+        } else {
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{:.3}\" cy=\"{:.3}\" r=\"0.2\" fill=\"gray\"/>",
+                y, x
+            );
+        }
+    }
+
+    let footprint = robot.footprint();
+    let (robot_length, robot_width) = (feet(footprint.length), feet(footprint.width));
+    let driven: Vec<_> = path.points.iter().filter(|p| !p.interpolated).collect();
+    for (i, w) in driven.windows(2).enumerate() {
+        if i % OUTLINE_EVERY != 0 && i != driven.len() - 2 {
+            continue;
+        }
+        let (a, b) = (w[0], w[1]);
+        let heading = (b.y - a.y).atan2(b.x - a.x);
+        let _ = writeln!(
+            svg,
+            "<polygon points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"0.04\"/>",
+            rectangle(a.x, a.y, heading, robot_length, robot_width),
+            if a.reversed { "orange" } else { "purple" }
+        );
+    }
+    let polyline = |points: &mut dyn Iterator<Item = (f64, f64)>| {
+        points
+            .map(|(x, y)| format!("{:.3},{:.3}", y, x))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let _ = writeln!(
+        svg,
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"firebrick\" stroke-width=\"0.1\"/>",
+        polyline(&mut driven.iter().map(|p| (p.x, p.y)))
+    );
+    let _ = writeln!(
+        svg,
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"green\" stroke-width=\"0.1\" \
+         stroke-dasharray=\"0.3\"/>",
+        polyline(
+            &mut path
+                .points
+                .iter()
+                .filter(|p| p.interpolated)
+                .map(|p| (p.x, p.y))
+        )
+    );
+
+    for marker in &path.markers {
+        let at = driven
+            .iter()
+            .find(|p| p.distance >= marker.distance)
+            .or_else(|| driven.last());
+        if let Some(p) = at {
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{:.3}\" cy=\"{:.3}\" r=\"0.25\" fill=\"steelblue\"/>\
+                 <text x=\"{:.3}\" y=\"{:.3}\" font-size=\"0.8\">{}</text>",
+                p.y,
+                p.x,
+                p.y + 0.4,
+                p.x - 0.4,
+                escape(&marker.name)
+            );
+        }
+    }
+    svg += overlay;
+    svg += "</svg>\n";
+    svg
+}
+
+/// Writes the preview of the path `name` as `<name>.svg` in `dir`.
+pub fn write(
+    path: &SampledPath,
+    name: &str,
+    field: &Field,
+    robot: &Robot,
+    dir: &Path,
+) -> Result<(), ExportError> {
+    let file = dir.join(format!("{}.svg", name));
+    fs::write(&file, render(path, name, field, robot)).map_err(|e| ExportError::io(&file, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape;
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(escape("leftSideStart"), "leftSideStart");
+    }
+}
@@ -0,0 +1,161 @@
+use crate::approach::Footprint;
+use crate::sim::DriveModel;
+use crate::trajectory::TrajectoryConfig;
+use crate::validate::RobotLimits;
+use crate::FEET_PER_METER;
+use dim::si;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The robot the paths are generated for, relative to the generator's working directory.
+pub const DEFAULT_ROBOT: &str = "robots/2018.json";
+
+/// A robot's size and what its drivetrain can do, in meters, radians and seconds. Everything
+/// that places, checks, follows or draws the robot takes it from here.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Robot {
+    pub name: String,
+    /// The frame perimeter, without bumpers.
+    frame: Size,
+    /// How far the bumpers stick out past the frame on every side.
+    bumper: f64,
+    /// Between the left and right wheels' contact patches.
+    track_width: f64,
+    wheel_radius: f64,
+    /// Limits for time parameterizing trajectories.
+    trajectory: TrajectoryConfig,
+    wheels: WheelLimits,
+    /// Limits on how sharply paths may turn.
+    turning: TurningLimits,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+struct Size {
+    /// Front to back.
+    length: f64,
+    /// Side to side.
+    width: f64,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WheelLimits {
+    /// At the tread.
+    max_velocity: f64,
+    max_acceleration: f64,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TurningLimits {
+    min_radius: f64,
+    /// The speed the robot is assumed to hold through turns.
+    cruise_velocity: f64,
+    max_angular_velocity: f64,
+    max_angular_acceleration: f64,
+}
+
+#[derive(Debug)]
+pub enum RobotError {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    /// A size or limit that must be positive isn't.
+    NotPositive(PathBuf, &'static str),
+    Negative(PathBuf, &'static str),
+}
+
+impl fmt::Display for RobotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RobotError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            RobotError::Json(path, e) => write!(f, "{}: {}", path.display(), e),
+            RobotError::NotPositive(path, what) => {
+                write!(f, "{}: {} must be positive", path.display(), what)
+            }
+            RobotError::Negative(path, what) => {
+                write!(f, "{}: {} can't be negative", path.display(), what)
+            }
+        }
+    }
+}
+
+impl Error for RobotError {}
+
+impl Robot {
+    pub fn load(path: &Path) -> Result<Self, RobotError> {
+        let bytes = fs::read(path).map_err(|e| RobotError::Io(path.to_owned(), e))?;
+        let robot: Robot =
+            serde_json::from_slice(&bytes).map_err(|e| RobotError::Json(path.to_owned(), e))?;
+        let positive = [
+            ("frame length", robot.frame.length),
+            ("frame width", robot.frame.width),
+            ("track width", robot.track_width),
+            ("wheel radius", robot.wheel_radius),
+            ("trajectory max velocity", robot.trajectory.max_velocity),
+            (
+                "trajectory max acceleration",
+                robot.trajectory.max_acceleration,
+            ),
+            (
+                "trajectory max centripetal acceleration",
+                robot.trajectory.max_centripetal_acceleration,
+            ),
+            ("wheel max velocity", robot.wheels.max_velocity),
+            ("wheel max acceleration", robot.wheels.max_acceleration),
+            ("turning cruise velocity", robot.turning.cruise_velocity),
+            (
+                "turning max angular velocity",
+                robot.turning.max_angular_velocity,
+            ),
+            (
+                "turning max angular acceleration",
+                robot.turning.max_angular_acceleration,
+            ),
+        ];
+        for &(what, value) in &positive {
+            if value <= 0.0 {
+                return Err(RobotError::NotPositive(path.to_owned(), what));
+            }
+        }
+        if robot.bumper < 0.0 {
+            return Err(RobotError::Negative(path.to_owned(), "bumper"));
+        }
+        Ok(robot)
+    }
+
+    /// The outline of the robot with its bumpers on.
+    pub fn footprint(&self) -> Footprint {
+        Footprint {
+            length: (self.frame.length + 2. * self.bumper) * si::M,
+            width: (self.frame.width + 2. * self.bumper) * si::M,
+        }
+    }
+
+    pub fn trajectory(&self) -> TrajectoryConfig {
+        self.trajectory
+    }
+
+    pub fn drive(&self) -> DriveModel {
+        DriveModel {
+            track_width: self.track_width,
+            wheel_radius: self.wheel_radius,
+            max_wheel_velocity: self.wheels.max_velocity,
+            max_wheel_acceleration: self.wheels.max_acceleration,
+        }
+    }
+
+    /// The turning limits in feet, which is what sampled paths are checked in.
+    pub fn limits(&self) -> RobotLimits {
+        RobotLimits {
+            min_turning_radius: self.turning.min_radius * FEET_PER_METER,
+            cruise_velocity: self.turning.cruise_velocity * FEET_PER_METER,
+            max_angular_velocity: self.turning.max_angular_velocity,
+            max_angular_acceleration: self.turning.max_angular_acceleration,
+        }
+    }
+}
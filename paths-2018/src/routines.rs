@@ -0,0 +1,441 @@
+//! Autos as the robot code picks them at the start of a match. A routine is the goals to score
+//! on in order; which paths it drives depends on where the robot was placed and which side of
+//! the field the game data lit our plates on. Paths are named after the leg they drive, from a
+//! start or a goal to a goal on one side, e.g. `rightToLeftScale` or `rightScaleToRightSwitch`,
+//! so a routine can run from wherever every one of its legs has been generated. Each routine
+//! declares the starts and layouts it's meant for, and is missing a leg if one of those has no
+//! path.
+//!
+//! Declaring a routine doesn't generate its legs. They're still written by hand in `autos`,
+//! since most need waypoints of their own to get around the switch or down the lane, and a
+//! routine only looks them up by name. A leg nobody has written is reported missing, and fails
+//! the export with `--strict`.
+
+use crate::error::ExportError;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The manifest of every routine, written into the output directory.
+pub const MANIFEST: &str = "routines.json";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldSide {
+    Left,
+    Right,
+}
+
+impl FieldSide {
+    fn name(self) -> &'static str {
+        match self {
+            FieldSide::Left => "Left",
+            FieldSide::Right => "Right",
+        }
+    }
+
+    /// As the game data gives it.
+    fn letter(self) -> char {
+        match self {
+            FieldSide::Left => 'L',
+            FieldSide::Right => 'R',
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Goal {
+    Switch,
+    Scale,
+}
+
+impl Goal {
+    fn name(self) -> &'static str {
+        match self {
+            Goal::Switch => "Switch",
+            Goal::Scale => "Scale",
+        }
+    }
+}
+
+/// Where the robot is placed against our alliance wall.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Start {
+    Left,
+    Center,
+    Right,
+}
+
+impl Start {
+    pub const ALL: &'static [Start] = &[Start::Left, Start::Center, Start::Right];
+
+    /// The pose the robot starts at.
+    pub fn pose(self) -> &'static str {
+        match self {
+            Start::Left => "leftSideStart",
+            Start::Center => "centerStart",
+            Start::Right => "rightSideStart",
+        }
+    }
+
+    /// How paths leaving the start begin their names.
+    fn leg_name(self) -> &'static str {
+        match self {
+            Start::Left => "left",
+            Start::Center => "center",
+            Start::Right => "right",
+        }
+    }
+
+    /// The side of the field a side start is on.
+    fn side(self) -> Option<FieldSide> {
+        match self {
+            Start::Left => Some(FieldSide::Left),
+            Start::Center => None,
+            Start::Right => Some(FieldSide::Right),
+        }
+    }
+}
+
+/// Which side our switch and scale plates are on, the first two letters of the game data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FieldConfig {
+    pub switch: FieldSide,
+    pub scale: FieldSide,
+}
+
+impl FieldConfig {
+    pub const ALL: &'static [FieldConfig] = &[
+        FieldConfig::new(FieldSide::Left, FieldSide::Left),
+        FieldConfig::new(FieldSide::Left, FieldSide::Right),
+        FieldConfig::new(FieldSide::Right, FieldSide::Left),
+        FieldConfig::new(FieldSide::Right, FieldSide::Right),
+    ];
+
+    const fn new(switch: FieldSide, scale: FieldSide) -> Self {
+        Self { switch, scale }
+    }
+
+    fn side(self, goal: Goal) -> FieldSide {
+        match goal {
+            Goal::Switch => self.switch,
+            Goal::Scale => self.scale,
+        }
+    }
+
+    /// The game data's letters, e.g. `LR` for our switch on the left and the scale on the right.
+    pub fn name(self) -> String {
+        [self.switch.letter(), self.scale.letter()].iter().collect()
+    }
+}
+
+/// Goals to score on one after another.
+pub struct Routine {
+    pub name: &'static str,
+    pub goals: &'static [Goal],
+    /// Whether the routine is meant to be run from a start with a layout. Anywhere else it's
+    /// left out of the manifest rather than missing legs.
+    pub runs: fn(Start, FieldConfig) -> bool,
+}
+
+pub const ROUTINES: &[Routine] = &[
+    // from the side, only a switch on our side is in reach
+    Routine {
+        name: "switch",
+        goals: &[Goal::Switch],
+        runs: |start, field| start.side().is_none_or(|side| side == field.switch),
+    },
+    Routine {
+        name: "scale",
+        goals: &[Goal::Scale],
+        runs: |start, _| start != Start::Center,
+    },
+    // the second cube comes from behind the switch on the scale's side
+    Routine {
+        name: "scaleThenSwitch",
+        goals: &[Goal::Scale, Goal::Switch],
+        runs: |start, field| start != Start::Center && field.switch == field.scale,
+    },
+];
+
+impl Routine {
+    /// The paths driven, in order, to run the routine from `start` with the plates lit as in
+    /// `field`, or every leg that isn't one of the paths `defined`.
+    pub fn expand(
+        &self,
+        start: Start,
+        field: FieldConfig,
+        defined: impl Fn(&str) -> bool,
+    ) -> Result<Vec<String>, Vec<String>> {
+        let mut from = start.leg_name().to_owned();
+        let mut legs = Vec::new();
+        let mut missing = Vec::new();
+        for &goal in self.goals {
+            let side = field.side(goal);
+            let leg = format!("{}To{}{}", from, side.name(), goal.name());
+            if defined(&leg) {
+                legs.push(leg);
+            } else {
+                missing.push(leg);
+            }
+            from = format!("{}{}", side.name().to_lowercase(), goal.name());
+        }
+        if missing.is_empty() {
+            Ok(legs)
+        } else {
+            Err(missing)
+        }
+    }
+
+    /// Where the routine isn't meant to be run, as `start with layout` names.
+    fn skipped(&self) -> Vec<String> {
+        let mut skipped = Vec::new();
+        for &start in Start::ALL {
+            for &field in FieldConfig::ALL {
+                if !(self.runs)(start, field) {
+                    skipped.push(format!("{} with {}", start.pose(), field.name()));
+                }
+            }
+        }
+        skipped
+    }
+}
+
+/// One way a routine can be run.
+pub struct Entry {
+    pub start: Start,
+    pub field: FieldConfig,
+    pub routine: &'static str,
+    /// Path names, in the order they're driven.
+    pub paths: Vec<String>,
+}
+
+/// A start and layout a routine is meant to be run from without a path for every leg.
+#[derive(Debug)]
+pub struct Unrunnable {
+    pub start: Start,
+    pub field: FieldConfig,
+    pub routine: &'static str,
+    pub missing: Vec<String>,
+}
+
+impl Unrunnable {
+    /// Names the routine, start and layout.
+    pub fn name(&self) -> String {
+        format!(
+            "routine {} from {} with {}",
+            self.routine,
+            self.start.pose(),
+            self.field.name()
+        )
+    }
+}
+
+/// Every routine expanded over the starts and layouts it's meant for.
+pub struct Expansion {
+    pub entries: Vec<Entry>,
+    pub unrunnable: Vec<Unrunnable>,
+}
+
+/// Expands every routine over every start and field layout it's meant for, with the paths
+/// `defined`.
+pub fn expand_all(defined: impl Fn(&str) -> bool) -> Expansion {
+    let mut expansion = Expansion {
+        entries: Vec::new(),
+        unrunnable: Vec::new(),
+    };
+    for routine in ROUTINES {
+        for &start in Start::ALL {
+            for &field in FieldConfig::ALL {
+                if !(routine.runs)(start, field) {
+                    continue;
+                }
+                match routine.expand(start, field, &defined) {
+                    Ok(paths) => expansion.entries.push(Entry {
+                        start,
+                        field,
+                        routine: routine.name,
+                        paths,
+                    }),
+                    Err(missing) => expansion.unrunnable.push(Unrunnable {
+                        start,
+                        field,
+                        routine: routine.name,
+                        missing,
+                    }),
+                }
+            }
+        }
+    }
+    expansion
+}
+
+/// Prints how many of the starts and field layouts each routine can be run from, naming those
+/// it isn't meant for and those missing legs.
+pub fn print_summary(expansion: &Expansion) {
+    let layouts = Start::ALL.len() * FieldConfig::ALL.len();
+    for routine in ROUTINES {
+        let runnable = expansion
+            .entries
+            .iter()
+            .filter(|e| e.routine == routine.name)
+            .count();
+        println!(
+            "routine {}: runs from {} of {} starts and field layouts",
+            routine.name, runnable, layouts
+        );
+        let skipped = routine.skipped();
+        if !skipped.is_empty() {
+            println!("    not meant for {}", skipped.join(", "));
+        }
+        for unrunnable in &expansion.unrunnable {
+            if unrunnable.routine == routine.name {
+                println!(
+                    "    missing {} from {} with {}",
+                    unrunnable.missing.join(", "),
+                    unrunnable.start.pose(),
+                    unrunnable.field.name()
+                );
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    start: &'static str,
+    field: String,
+    routine: &'static str,
+    paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    routines: Vec<ManifestEntry>,
+}
+
+/// Writes the manifest into `dir`, giving each path as the file `path_file` names for it.
+pub fn write(
+    entries: &[Entry],
+    path_file: impl Fn(&str) -> String,
+    dir: &Path,
+) -> Result<(), ExportError> {
+    let manifest = Manifest {
+        routines: entries
+            .iter()
+            .map(|entry| ManifestEntry {
+                start: entry.start.pose(),
+                field: entry.field.name(),
+                routine: entry.routine,
+                paths: entry.paths.iter().map(|name| path_file(name)).collect(),
+            })
+            .collect(),
+    };
+    let file = dir.join(MANIFEST);
+    serde_json::to_vec_pretty(&manifest)
+        .map_err(io::Error::from)
+        .and_then(|json| fs::write(&file, json))
+        .map_err(|e| ExportError::io(&file, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATHS: &[&str] = &[
+        "centerToLeftSwitch",
+        "centerToRightSwitch",
+        "rightToRightScale",
+        "leftToLeftScale",
+        "rightToLeftScale",
+        "leftToRightScale",
+        "rightToRightSwitch",
+        "leftToLeftSwitch",
+        "rightScaleToRightSwitch",
+        "leftScaleToLeftSwitch",
+    ];
+
+    fn defined(leg: &str) -> bool {
+        PATHS.contains(&leg)
+    }
+
+    fn routine(name: &str) -> &'static Routine {
+        ROUTINES.iter().find(|r| r.name == name).unwrap()
+    }
+
+    fn layout(name: &str) -> FieldConfig {
+        *FieldConfig::ALL.iter().find(|f| f.name() == name).unwrap()
+    }
+
+    fn mirrored(leg: &str) -> String {
+        leg.replace("left", "_l")
+            .replace("Left", "_L")
+            .replace("right", "left")
+            .replace("Right", "Left")
+            .replace("_l", "right")
+            .replace("_L", "Right")
+    }
+
+    #[test]
+    fn expands_legs_in_order() {
+        let legs = routine("scaleThenSwitch").expand(Start::Left, layout("RR"), defined);
+        assert_eq!(
+            legs.unwrap(),
+            ["leftToRightScale", "rightScaleToRightSwitch"]
+        );
+        let legs = routine("switch").expand(Start::Center, layout("LR"), defined);
+        assert_eq!(legs.unwrap(), ["centerToLeftSwitch"]);
+    }
+
+    #[test]
+    fn mirrored_layouts_drive_mirrored_legs() {
+        let mirror = |side| match side {
+            FieldSide::Left => FieldSide::Right,
+            FieldSide::Right => FieldSide::Left,
+        };
+        for routine in ROUTINES {
+            for &field in FieldConfig::ALL {
+                let other = FieldConfig::new(mirror(field.switch), mirror(field.scale));
+                assert_eq!(
+                    (routine.runs)(Start::Right, field),
+                    (routine.runs)(Start::Left, other)
+                );
+                let right = routine.expand(Start::Right, field, defined);
+                let left = routine.expand(Start::Left, other, defined);
+                let mirror_all = |legs: Vec<String>| -> Vec<String> {
+                    legs.iter().map(|leg| mirrored(leg)).collect()
+                };
+                assert_eq!(right.map(mirror_all).map_err(mirror_all), left);
+            }
+        }
+    }
+
+    #[test]
+    fn names_missing_legs() {
+        let without = |leg: &str| leg != "leftScaleToLeftSwitch" && defined(leg);
+        let legs = routine("scaleThenSwitch").expand(Start::Right, layout("LL"), without);
+        assert_eq!(legs.unwrap_err(), ["leftScaleToLeftSwitch"]);
+
+        let expansion = expand_all(without);
+        let names: Vec<String> = expansion.unrunnable.iter().map(|u| u.name()).collect();
+        assert_eq!(
+            names,
+            [
+                "routine scaleThenSwitch from leftSideStart with LL",
+                "routine scaleThenSwitch from rightSideStart with LL",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_combination_meant_to_run_does() {
+        let expansion = expand_all(defined);
+        assert!(expansion.unrunnable.is_empty());
+        // a center start can only reach the switch
+        assert!(expansion
+            .entries
+            .iter()
+            .filter(|e| e.start == Start::Center)
+            .all(|e| e.routine == "switch"));
+    }
+}
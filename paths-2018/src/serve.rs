@@ -0,0 +1,294 @@
+//! A web page on localhost drawing every generated path over the field, for anyone without
+//! Python to run `visualize.py`. Paths are read back from the `.114path` files in the output
+//! directory on every request, so with `watch` running in another terminal the page follows
+//! edits; it reloads itself whenever the output directory changes.
+
+use crate::export;
+use crate::field::Field;
+use crate::parse;
+use crate::path::SampledPath;
+use crate::preview::{self, escape};
+use crate::robot::Robot;
+use crate::trajectory;
+use crate::FEET_PER_METER;
+use dim::si;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+pub const DEFAULT_PORT: u16 = 8114;
+/// The picture of the field, relative to the working directory as for `visualize.py`.
+const FIELD_IMAGE: &str = "fieldCropped.png";
+/// How long a browser gets to send its request before it is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the page asks whether the outputs changed, in milliseconds.
+const RELOAD_POLL_MS: u32 = 1000;
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 1em 2em; }\n\
+                     svg { max-width: 100%; height: auto; border: 1px solid #ccc; }\n\
+                     circle.sample { fill: transparent; }\n\
+                     circle.sample:hover { fill: gold; }\n\
+                     .error { color: firebrick; }\n";
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    fn error(status: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: message.into().into_bytes(),
+        }
+    }
+
+    fn send(&self, stream: &mut TcpStream) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        )?;
+        stream.write_all(&self.body)?;
+        stream.flush()
+    }
+}
+
+/// What the page is drawn from.
+struct Site<'a> {
+    out_dir: &'a Path,
+    field: &'a str,
+    robot: &'a str,
+}
+
+impl Site<'_> {
+    fn respond(&self, method: &str, target: &str) -> Response {
+        if method != "GET" {
+            return Response::error("405 Method Not Allowed", "only GET is supported");
+        }
+        // query strings only defeat caching
+        match target.split('?').next().unwrap_or("") {
+            "/" => self.index(),
+            "/stamp" => match self.stamp() {
+                Ok(stamp) => Response::ok("text/plain; charset=utf-8", stamp),
+                Err(e) => Response::error("500 Internal Server Error", e.to_string()),
+            },
+            "/field.png" => match fs::read(FIELD_IMAGE) {
+                Ok(image) => Response::ok("image/png", image),
+                Err(e) => Response::error("404 Not Found", format!("{}: {}", FIELD_IMAGE, e)),
+            },
+            _ => Response::error("404 Not Found", "not found"),
+        }
+    }
+
+    /// The `.114path` files in the output directory, by name.
+    fn path_files(&self) -> io::Result<Vec<(String, PathBuf)>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(self.out_dir)? {
+            let file = entry?.path();
+            if file.extension().is_some_and(|ext| ext == "114path") {
+                if let Some(name) = file.file_stem().and_then(|s| s.to_str()) {
+                    files.push((name.to_owned(), file.clone()));
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Changes whenever a file is written to, added to or removed from the output directory,
+    /// or the field file or robot profile is edited.
+    fn stamp(&self) -> io::Result<String> {
+        let mut files = 0;
+        let modified = |file| fs::metadata(file).and_then(|m| m.modified()).ok();
+        let mut latest = modified(self.field).max(modified(self.robot));
+        for entry in fs::read_dir(self.out_dir)? {
+            let modified = entry?.metadata()?.modified()?;
+            latest = latest.max(Some(modified));
+            files += 1;
+        }
+        let nanos = latest
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos());
+        Ok(format!("{} {}", files, nanos))
+    }
+
+    fn index(&self) -> Response {
+        let field = match Field::load(Path::new(self.field)) {
+            Ok(field) => field,
+            Err(e) => return Response::error("500 Internal Server Error", e.to_string()),
+        };
+        let robot = match Robot::load(Path::new(self.robot)) {
+            Ok(robot) => robot,
+            Err(e) => return Response::error("500 Internal Server Error", e.to_string()),
+        };
+        let files = match self.path_files() {
+            Ok(files) => files,
+            Err(e) => {
+                let message = format!("{}: {}", self.out_dir.display(), e);
+                return Response::error("500 Internal Server Error", message);
+            }
+        };
+        let stamp = self.stamp().unwrap_or_default();
+        let footprint = robot.footprint();
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>paths</title>\n\
+             <style>\n{}</style>\n</head>\n<body>\n<h1>{}</h1>\n\
+             <p>{}, {:.3} by {:.3} m with bumpers</p>\n",
+            STYLE,
+            escape(&field.name),
+            escape(&robot.name),
+            *(footprint.length / si::M),
+            *(footprint.width / si::M)
+        );
+        if files.is_empty() {
+            let _ = writeln!(
+                html,
+                "<p>No paths in {} yet. Generate them in the 114path format.</p>",
+                escape(&self.out_dir.display().to_string())
+            );
+        } else {
+            let _ = writeln!(html, "<ul>");
+            for (name, _) in &files {
+                let _ = writeln!(html, "<li><a href=\"#{0}\">{0}</a></li>", escape(name));
+            }
+            let _ = writeln!(html, "</ul>");
+        }
+        for (name, file) in &files {
+            let _ = writeln!(html, "<section id=\"{0}\">\n<h2>{0}</h2>", escape(name));
+            match parse::read_114path(file) {
+                Ok(path) => html += &draw(&path, name, &field, &robot),
+                Err(e) => {
+                    let message = format!("{}: {}", file.display(), e);
+                    let _ = writeln!(html, "<p class=\"error\">{}</p>", escape(&message));
+                }
+            }
+            let _ = writeln!(html, "</section>");
+        }
+        let _ = write!(
+            html,
+            "<script>\n\
+             const stamp = \"{}\";\n\
+             setInterval(() => fetch(\"/stamp\")\n  \
+               .then(r => r.text())\n  \
+               .then(s => {{ if (s !== stamp) location.reload(); }})\n  \
+               .catch(() => {{}}), {});\n\
+             </script>\n</body>\n</html>\n",
+            stamp, RELOAD_POLL_MS
+        );
+        Response::ok("text/html; charset=utf-8", html)
+    }
+}
+
+/// The path's preview with a hover target on each sample of its trajectory, whose tooltip gives
+/// the distance, speed and curvature there in feet, like the rest of the path.
+fn draw(path: &SampledPath, name: &str, field: &Field, robot: &Robot) -> String {
+    let states = match trajectory::parameterize(path, &robot.trajectory()) {
+        Ok(states) => states,
+        Err(e) => {
+            let preview = preview::render_over(path, name, field, robot, "/field.png", "");
+            return format!(
+                "<p class=\"error\">{}</p>\n{}",
+                escape(&e.to_string()),
+                preview
+            );
+        }
+    };
+    let mut overlay = String::new();
+    let mut distance = 0.0;
+    let mut last = None;
+    for state in &states {
+        let x = state.pose.translation.x * FEET_PER_METER;
+        let y = state.pose.translation.y * FEET_PER_METER;
+        if let Some((lx, ly)) = last {
+            distance += f64::hypot(x - lx, y - ly);
+        }
+        last = Some((x, y));
+        // the drawing's x is downfield, its y across the field
+        let _ = writeln!(
+            overlay,
+            "<circle class=\"sample\" cx=\"{:.3}\" cy=\"{:.3}\" r=\"0.2\"><title>\
+             distance {:.2} ft\nvelocity {:.2} ft/s\ncurvature {:.3} 1/ft\ntime {:.2} s\
+             </title></circle>",
+            y,
+            x,
+            distance,
+            state.velocity * FEET_PER_METER,
+            state.curvature / FEET_PER_METER,
+            state.time
+        );
+    }
+    preview::render_over(path, name, field, robot, "/field.png", &overlay)
+}
+
+fn handle(site: &Site, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // the headers don't matter, but are read so the browser isn't cut off mid request
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut words = request.split_whitespace();
+    let response = match (words.next(), words.next()) {
+        (Some(method), Some(target)) => site.respond(method, target),
+        _ => Response::error("400 Bad Request", "malformed request"),
+    };
+    response.send(&mut stream)
+}
+
+/// Serves the page on `port` of localhost until killed. Only returns, with `false`, if it can't
+/// start serving.
+pub fn serve(out_dir: &Path, field: &str, robot: &str, port: u16) -> bool {
+    if let Err(e) = export::check_output_dir(out_dir) {
+        eprintln!("{}", e);
+        return false;
+    }
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("can't listen on port {}: {}", port, e);
+            return false;
+        }
+    };
+    println!(
+        "serving the paths in {} at http://localhost:{}/",
+        out_dir.display(),
+        port
+    );
+
+    let site = Site {
+        out_dir,
+        field,
+        robot,
+    };
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| handle(&site, stream));
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    }
+    true
+}
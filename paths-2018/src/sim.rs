@@ -0,0 +1,464 @@
+use crate::error::ExportError;
+use crate::path::SampledPath;
+use crate::trajectory::{self, TrajectoryConfig, TrajectoryState};
+use coord_frames::Angle;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// Simulation time step, in seconds.
+const DT: f64 = 0.005;
+/// How long the robot gets after the trajectory ends to reach the final pose.
+const SETTLE_TIME: f64 = 2.0;
+/// How close to the final position counts as done, in meters.
+const ARRIVED: f64 = 0.03;
+
+/// A differential drive, in meters and seconds.
+#[derive(Debug, Copy, Clone)]
+pub struct DriveModel {
+    pub track_width: f64,
+    pub wheel_radius: f64,
+    pub max_wheel_velocity: f64,
+    pub max_wheel_acceleration: f64,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Controller {
+    /// Steers toward the point `lookahead` meters ahead on the path, at the speed planned for
+    /// the closest point.
+    PurePursuit { lookahead: f64 },
+    /// The nonlinear time-varying tracker from Ramsete's paper, which WPILib also ships.
+    Ramsete { b: f64, zeta: f64 },
+}
+
+impl Controller {
+    pub const NAMES: &'static [&'static str] = &["pure-pursuit", "ramsete"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "pure-pursuit" => Controller::PurePursuit { lookahead: 0.5 },
+            "ramsete" => Controller::Ramsete { b: 2.0, zeta: 0.7 },
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Controller::PurePursuit { .. } => "pure-pursuit",
+            Controller::Ramsete { .. } => "ramsete",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SimConfig {
+    pub drive: DriveModel,
+    pub controller: Controller,
+    /// Delay between the controller deciding on wheel speeds and the wheels getting them.
+    pub latency: f64,
+    /// Fraction of wheel motion lost to the carpet.
+    pub slip: f64,
+}
+
+/// One step of the simulation, in meters, radians and seconds.
+#[derive(Debug, Copy, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceRow {
+    pub time: f64,
+    pub x: f64,
+    pub y: f64,
+    pub heading: f64,
+    pub reference_x: f64,
+    pub reference_y: f64,
+    pub left_velocity: f64,
+    pub right_velocity: f64,
+    /// How fast the wheels turn, to compare against encoders.
+    pub left_wheel_speed: f64,
+    pub right_wheel_speed: f64,
+    pub cross_track_error: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimResult {
+    pub trace: Vec<TraceRow>,
+    pub max_cross_track_error: f64,
+    pub rms_cross_track_error: f64,
+    pub final_position_error: f64,
+    pub final_heading_error: Angle,
+    /// When the robot reached the end, or `None` if it never did.
+    pub completion_time: Option<f64>,
+    pub trajectory_time: f64,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Pose {
+    x: f64,
+    y: f64,
+    heading: Angle,
+}
+
+/// The reference at `time`, interpolated between the trajectory's states.
+fn sample(states: &[TrajectoryState], time: f64) -> TrajectoryState {
+    let i = states
+        .iter()
+        .position(|s| s.time > time)
+        .unwrap_or(states.len());
+    if i == 0 {
+        return states[0];
+    }
+    if i == states.len() {
+        return TrajectoryState {
+            velocity: 0.0,
+            acceleration: 0.0,
+            ..states[i - 1]
+        };
+    }
+    let (a, b) = (states[i - 1], states[i]);
+    let f = (time - a.time) / (b.time - a.time);
+    let lerp = |p: f64, q: f64| p + (q - p) * f;
+    let mut state = a;
+    state.time = time;
+    state.velocity = lerp(a.velocity, b.velocity);
+    state.curvature = lerp(a.curvature, b.curvature);
+    state.pose.translation.x = lerp(a.pose.translation.x, b.pose.translation.x);
+    state.pose.translation.y = lerp(a.pose.translation.y, b.pose.translation.y);
+    let (ha, hb) = (
+        Angle::from_radians(a.pose.rotation.radians),
+        Angle::from_radians(b.pose.rotation.radians),
+    );
+    state.pose.rotation.radians = (ha + ha.shortest_diff(hb) * f).radians();
+    state
+}
+
+/// Distance from `(x, y)` to the polyline through the trajectory's positions.
+fn cross_track(states: &[TrajectoryState], x: f64, y: f64) -> f64 {
+    states
+        .windows(2)
+        .map(|w| {
+            let (a, b) = (w[0].pose.translation, w[1].pose.translation);
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            let len2 = dx * dx + dy * dy;
+            let f = if len2 > 0.0 {
+                (((x - a.x) * dx + (y - a.y) * dy) / len2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            f64::sqrt((a.x + f * dx - x).powi(2) + (a.y + f * dy - y).powi(2))
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Linear and angular velocity commands for the robot at `pose`.
+fn control(
+    controller: Controller,
+    states: &[TrajectoryState],
+    reference: &TrajectoryState,
+    pose: Pose,
+    progress: &mut usize,
+) -> (f64, f64) {
+    let v_ref = reference.velocity;
+    let w_ref = reference.velocity * reference.curvature;
+    let (xr, yr) = (reference.pose.translation.x, reference.pose.translation.y);
+    match controller {
+        Controller::PurePursuit { lookahead } => {
+            // keep to the part of the path the robot is on, so it can't skip across a cusp
+            let dist = |s: &TrajectoryState| {
+                f64::sqrt(
+                    (s.pose.translation.x - pose.x).powi(2)
+                        + (s.pose.translation.y - pose.y).powi(2),
+                )
+            };
+            let window = (*progress + 50).min(states.len());
+            *progress = (*progress..window)
+                .min_by(|&a, &b| dist(&states[a]).partial_cmp(&dist(&states[b])).unwrap())
+                .unwrap_or(*progress);
+            // the closest point may be a stop, so take the speed of the point after it too
+            let next = states[(*progress + 1).min(states.len() - 1)];
+            let speed = states[*progress].velocity.abs().max(next.velocity.abs());
+            let reversed = next.velocity < 0.0;
+            let goal = states[*progress..]
+                .iter()
+                .find(|s| {
+                    dist(s) >= lookahead || (s.velocity != 0.0 && (s.velocity < 0.0) != reversed)
+                })
+                .unwrap_or_else(|| states.last().unwrap());
+
+            let travel = if reversed {
+                pose.heading + Angle::from_degrees(180.)
+            } else {
+                pose.heading
+            };
+            let (dx, dy) = (
+                goal.pose.translation.x - pose.x,
+                goal.pose.translation.y - pose.y,
+            );
+            let side = -travel.sin() * dx + travel.cos() * dy;
+            let d2 = dx * dx + dy * dy;
+            let curvature = if d2 > 1e-12 { 2. * side / d2 } else { 0.0 };
+            (if reversed { -speed } else { speed }, speed * curvature)
+        }
+        Controller::Ramsete { b, zeta } => {
+            let (dx, dy) = (xr - pose.x, yr - pose.y);
+            let ex = pose.heading.cos() * dx + pose.heading.sin() * dy;
+            let ey = -pose.heading.sin() * dx + pose.heading.cos() * dy;
+            let e_theta = pose
+                .heading
+                .shortest_diff(Angle::from_radians(reference.pose.rotation.radians))
+                .radians();
+            let k = 2. * zeta * (w_ref * w_ref + b * v_ref * v_ref).sqrt();
+            let sinc = if e_theta.abs() < 1e-9 {
+                1.0
+            } else {
+                e_theta.sin() / e_theta
+            };
+            (
+                v_ref * e_theta.cos() + k * ex,
+                w_ref + k * e_theta + b * v_ref * sinc * ey,
+            )
+        }
+    }
+}
+
+/// Drives a simulated robot along `path`, time parameterized with `trajectory`, starting on the
+/// first pose of the path. Fails if the path is too short to parameterize.
+pub fn simulate(
+    path: &SampledPath,
+    trajectory: &TrajectoryConfig,
+    config: &SimConfig,
+) -> Result<SimResult, ExportError> {
+    let states = trajectory::parameterize(path, trajectory)?;
+    let (first, last) = (states[0], states[states.len() - 1]);
+    let drive = config.drive;
+
+    let mut pose = Pose {
+        x: first.pose.translation.x,
+        y: first.pose.translation.y,
+        heading: Angle::from_radians(first.pose.rotation.radians),
+    };
+    let mut wheels = (0.0, 0.0);
+    let delay = (config.latency / DT).round() as usize;
+    let mut commands: VecDeque<(f64, f64)> = VecDeque::from(vec![(0.0, 0.0); delay]);
+    let mut progress = 0;
+
+    let mut result = SimResult {
+        trace: Vec::new(),
+        max_cross_track_error: 0.0,
+        rms_cross_track_error: 0.0,
+        final_position_error: 0.0,
+        final_heading_error: Angle::default(),
+        completion_time: None,
+        trajectory_time: last.time,
+    };
+    let mut squared_error = 0.0;
+    let mut time = 0.0;
+    while time <= last.time + SETTLE_TIME {
+        let reference = sample(&states, time);
+        let error = cross_track(&states, pose.x, pose.y);
+        result.max_cross_track_error = result.max_cross_track_error.max(error);
+        squared_error += error * error;
+        result.trace.push(TraceRow {
+            time,
+            x: pose.x,
+            y: pose.y,
+            heading: pose.heading.radians(),
+            reference_x: reference.pose.translation.x,
+            reference_y: reference.pose.translation.y,
+            left_velocity: wheels.0,
+            right_velocity: wheels.1,
+            left_wheel_speed: wheels.0 / drive.wheel_radius,
+            right_wheel_speed: wheels.1 / drive.wheel_radius,
+            cross_track_error: error,
+        });
+
+        let to_end = f64::sqrt(
+            (last.pose.translation.x - pose.x).powi(2) + (last.pose.translation.y - pose.y).powi(2),
+        );
+        if time >= last.time && to_end < ARRIVED {
+            result.completion_time = Some(time);
+            break;
+        }
+
+        let (v, w) = control(config.controller, &states, &reference, pose, &mut progress);
+        let mut left = v - w * drive.track_width / 2.;
+        let mut right = v + w * drive.track_width / 2.;
+        // saturate without changing the ratio between the wheels, so the robot keeps its arc
+        let fastest = left.abs().max(right.abs());
+        if fastest > drive.max_wheel_velocity {
+            left *= drive.max_wheel_velocity / fastest;
+            right *= drive.max_wheel_velocity / fastest;
+        }
+        commands.push_back((left, right));
+        let (left, right) = commands.pop_front().unwrap_or((left, right));
+
+        let max_change = drive.max_wheel_acceleration * DT;
+        let approach = |current: f64, target: f64| {
+            current + (target - current).max(-max_change).min(max_change)
+        };
+        wheels = (approach(wheels.0, left), approach(wheels.1, right));
+
+        let grip = 1. - config.slip;
+        let v = (wheels.0 + wheels.1) / 2. * grip;
+        let w = (wheels.1 - wheels.0) / drive.track_width * grip;
+        let mid = pose.heading + Angle::from_radians(w * DT / 2.);
+        pose.x += v * mid.cos() * DT;
+        pose.y += v * mid.sin() * DT;
+        pose.heading = pose.heading + Angle::from_radians(w * DT);
+        time += DT;
+    }
+
+    result.rms_cross_track_error = (squared_error / result.trace.len() as f64).sqrt();
+    result.final_position_error = f64::sqrt(
+        (last.pose.translation.x - pose.x).powi(2) + (last.pose.translation.y - pose.y).powi(2),
+    );
+    result.final_heading_error = pose
+        .heading
+        .shortest_diff(Angle::from_radians(last.pose.rotation.radians));
+    Ok(result)
+}
+
+/// Writes the trace as CSV, for plotting.
+pub fn write_trace(result: &SimResult, file: &Path) -> Result<(), ExportError> {
+    let write = || -> csv::Result<()> {
+        let mut wtr = csv::Writer::from_path(file)?;
+        for row in &result.trace {
+            wtr.serialize(row)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    };
+    write().map_err(|e| ExportError::io(file, e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::Spline;
+    use crate::path::{PathChain, Segment};
+    use eta3_spline::MotionState;
+
+    const TRAJECTORY: TrajectoryConfig = TrajectoryConfig {
+        max_velocity: 3.,
+        max_acceleration: 2.,
+        max_centripetal_acceleration: 2.5,
+    };
+
+    const DRIVE: DriveModel = DriveModel {
+        track_width: 0.7,
+        wheel_radius: 0.0762,
+        max_wheel_velocity: 3.5,
+        max_wheel_acceleration: 6.,
+    };
+
+    const CONTROLLERS: &[Controller] = &[
+        Controller::PurePursuit { lookahead: 0.5 },
+        Controller::Ramsete { b: 2.0, zeta: 0.7 },
+    ];
+
+    fn state(x: f64, y: f64, t: f64) -> MotionState<f64> {
+        MotionState {
+            x,
+            y,
+            t,
+            k: 0.,
+            dk: 0.,
+        }
+    }
+
+    /// A path in feet from `from` to `to`, driven forwards or backwards.
+    fn path(from: MotionState<f64>, to: MotionState<f64>, reversed: bool) -> SampledPath {
+        let points = vec![from, to];
+        let segment = if reversed {
+            Segment::reverse(points, Spline::QuinticHermite, 300)
+        } else {
+            Segment::forward(points, Spline::QuinticHermite, 300)
+        };
+        PathChain::new(segment).sample().unwrap()
+    }
+
+    fn straight() -> SampledPath {
+        path(state(0., 0., 0.), state(15., 0., 0.), false)
+    }
+
+    fn curved() -> SampledPath {
+        path(state(0., 0., 0.), state(15., 10., 1.2), false)
+    }
+
+    fn run(path: &SampledPath, controller: Controller, latency: f64, slip: f64) -> SimResult {
+        let config = SimConfig {
+            drive: DRIVE,
+            controller,
+            latency,
+            slip,
+        };
+        simulate(path, &TRAJECTORY, &config).unwrap()
+    }
+
+    fn behind(result: &SimResult) -> f64 {
+        result
+            .trace
+            .iter()
+            .map(|row| f64::hypot(row.reference_x - row.x, row.reference_y - row.y))
+            .fold(0., f64::max)
+    }
+
+    #[test]
+    fn follows_straight_and_curved_paths() {
+        for &controller in CONTROLLERS {
+            for path in &[straight(), curved()] {
+                let result = run(path, controller, 0., 0.);
+                assert!(result.completion_time.is_some(), "{}", controller.name());
+                assert!(result.max_cross_track_error < 0.05, "{}", controller.name());
+                assert!(
+                    result.final_position_error < ARRIVED,
+                    "{}",
+                    controller.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn latency_and_slip_track_worse() {
+        let finished = |result: &SimResult| result.completion_time.unwrap_or(f64::INFINITY);
+        for &controller in CONTROLLERS {
+            let ideal = run(&curved(), controller, 0., 0.);
+            // reacting late to its error, the robot swings off the path
+            let late = run(&curved(), controller, 0.1, 0.);
+            assert!(
+                late.rms_cross_track_error > 2. * ideal.rms_cross_track_error,
+                "{}",
+                controller.name()
+            );
+            // losing grip, it keeps its arc but falls behind
+            let slipping = run(&curved(), controller, 0., 0.1);
+            assert!(behind(&slipping) > behind(&ideal), "{}", controller.name());
+            assert!(
+                finished(&slipping) > finished(&ideal),
+                "{}",
+                controller.name()
+            );
+        }
+    }
+
+    #[test]
+    fn backs_down_a_reversed_leg() {
+        let reversed = path(state(15., 5., 0.), state(0., 0., 0.), true);
+        for &controller in CONTROLLERS {
+            let result = run(&reversed, controller, 0., 0.);
+            assert!(result.completion_time.is_some(), "{}", controller.name());
+            assert!(result.max_cross_track_error < 0.05, "{}", controller.name());
+            // still facing the way it started rather than turned around
+            assert!(
+                result.final_heading_error.radians().abs() < 0.05,
+                "{}",
+                controller.name()
+            );
+            for step in result.trace.windows(2) {
+                let (from, to) = (step[0], step[1]);
+                let forward =
+                    (to.x - from.x) * from.heading.cos() + (to.y - from.y) * from.heading.sin();
+                assert!(forward <= 1e-9, "{} at {} s", controller.name(), from.time);
+            }
+        }
+    }
+}
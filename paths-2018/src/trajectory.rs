@@ -0,0 +1,295 @@
+use crate::error::ExportError;
+use crate::path::SampledPath;
+use crate::FEET_PER_METER;
+use coord_frames::Angle;
+use serde::{Deserialize, Serialize};
+
+/// Limits used to time parameterize a path, in meters and seconds.
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrajectoryConfig {
+    pub max_velocity: f64,
+    pub max_acceleration: f64,
+    /// Caps speed through turns, since `v^2 * curvature` is the sideways acceleration.
+    pub max_centripetal_acceleration: f64,
+}
+
+/// One state of a trajectory, laid out like WPILib's `Trajectory.State` so that a list of them
+/// serializes to WPILib's trajectory JSON.
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct TrajectoryState {
+    pub time: f64,
+    /// Negative while driving backwards.
+    pub velocity: f64,
+    pub acceleration: f64,
+    pub pose: Pose2d,
+    /// Relative to the robot's heading, in radians per meter.
+    pub curvature: f64,
+}
+
+/// Where each side of a differential drive is at one state of a trajectory, in meters and
+/// seconds. Positions are how far each side's wheels have rolled since the start.
+#[derive(Debug, Copy, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WheelState {
+    pub time: f64,
+    pub left_position: f64,
+    pub left_velocity: f64,
+    pub left_acceleration: f64,
+    pub right_position: f64,
+    pub right_velocity: f64,
+    pub right_acceleration: f64,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Pose2d {
+    pub translation: Translation2d,
+    pub rotation: Rotation2d,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Translation2d {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Rotation2d {
+    pub radians: f64,
+}
+
+/// Assigns a time, velocity and acceleration to every sample of a path.
+///
+/// End point interpolation is dropped, and the robot comes to a stop at both ends of the path
+/// and wherever it switches between driving forwards and backwards. Fails if fewer than two
+/// distinct points are driven.
+pub fn parameterize(
+    path: &SampledPath,
+    config: &TrajectoryConfig,
+) -> Result<Vec<TrajectoryState>, ExportError> {
+    // (x, y, distance, reversed) in meters, without repeated points
+    let mut pts: Vec<(f64, f64, f64, bool)> = Vec::new();
+    for p in path.points.iter().filter(|p| !p.interpolated) {
+        let p = (
+            p.x / FEET_PER_METER,
+            p.y / FEET_PER_METER,
+            p.distance / FEET_PER_METER,
+            p.reversed,
+        );
+        match pts.last() {
+            Some(last) if p.2 - last.2 < 1e-9 => (),
+            _ => pts.push(p),
+        }
+    }
+    let n = pts.len();
+    if n < 2 {
+        return Err(ExportError::TooShort { points: n });
+    }
+
+    // direction of travel and its rate of change with distance
+    let heading: Vec<Angle> = (0..n)
+        .map(|i| {
+            let (a, b) = (pts[i.saturating_sub(1)], pts[(i + 1).min(n - 1)]);
+            Angle::from_radians((b.1 - a.1).atan2(b.0 - a.0))
+        })
+        .collect();
+    let curvature: Vec<f64> = (0..n)
+        .map(|i| {
+            let (a, b) = (i.saturating_sub(1), (i + 1).min(n - 1));
+            heading[a].shortest_diff(heading[b]).radians() / (pts[b].2 - pts[a].2)
+        })
+        .collect();
+
+    let stop =
+        |i: usize| i == 0 || i == n - 1 || pts[i].3 != pts[i - 1].3 || pts[i].3 != pts[i + 1].3;
+    let mut velocity: Vec<f64> = (0..n)
+        .map(|i| {
+            if stop(i) {
+                0.0
+            } else {
+                let turn_limit = (config.max_centripetal_acceleration / curvature[i].abs()).sqrt();
+                config.max_velocity.min(turn_limit)
+            }
+        })
+        .collect();
+    // respect acceleration going forwards, then deceleration coming back
+    for i in 1..n {
+        let ds = pts[i].2 - pts[i - 1].2;
+        let reachable = (velocity[i - 1].powi(2) + 2. * config.max_acceleration * ds).sqrt();
+        velocity[i] = velocity[i].min(reachable);
+    }
+    for i in (0..n - 1).rev() {
+        let ds = pts[i + 1].2 - pts[i].2;
+        let reachable = (velocity[i + 1].powi(2) + 2. * config.max_acceleration * ds).sqrt();
+        velocity[i] = velocity[i].min(reachable);
+    }
+
+    let mut states = Vec::with_capacity(n);
+    let mut time = 0.0;
+    for i in 0..n {
+        let mut acceleration = 0.0;
+        if i > 0 {
+            let ds = pts[i].2 - pts[i - 1].2;
+            let (v0, v1) = (velocity[i - 1], velocity[i]);
+            let dt = if v0 + v1 > 1e-9 {
+                2. * ds / (v0 + v1)
+            } else {
+                // stopped at both ends, accelerate for half and decelerate for the other half
+                2. * (ds / config.max_acceleration).sqrt()
+            };
+            time += dt;
+            acceleration = (v1 - v0) / dt;
+        }
+        let (flip, sign) = if pts[i].3 {
+            (Angle::from_degrees(180.), -1.)
+        } else {
+            (Angle::default(), 1.)
+        };
+        states.push(TrajectoryState {
+            time,
+            velocity: velocity[i] * sign,
+            acceleration: acceleration * sign,
+            pose: Pose2d {
+                translation: Translation2d {
+                    x: pts[i].0,
+                    y: pts[i].1,
+                },
+                rotation: Rotation2d {
+                    radians: (heading[i] + flip).radians(),
+                },
+            },
+            curvature: curvature[i] * sign,
+        });
+    }
+    Ok(states)
+}
+
+/// What the wheels of a robot with `track_width` between its sides do to follow `states`, for
+/// controllers that run a velocity loop on each side.
+pub fn wheel_states(states: &[TrajectoryState], track_width: f64) -> Vec<WheelState> {
+    let sides = |s: &TrajectoryState| {
+        let turn = s.curvature * track_width / 2.;
+        (s.velocity * (1. - turn), s.velocity * (1. + turn))
+    };
+    let mut wheels: Vec<WheelState> = Vec::with_capacity(states.len());
+    for s in states {
+        let (left, right) = sides(s);
+        let mut wheel = WheelState {
+            time: s.time,
+            left_position: 0.0,
+            left_velocity: left,
+            left_acceleration: 0.0,
+            right_position: 0.0,
+            right_velocity: right,
+            right_acceleration: 0.0,
+        };
+        if let Some(last) = wheels.last() {
+            let dt = s.time - last.time;
+            wheel.left_position = last.left_position + (last.left_velocity + left) / 2. * dt;
+            wheel.right_position = last.right_position + (last.right_velocity + right) / 2. * dt;
+            wheel.left_acceleration = (left - last.left_velocity) / dt;
+            wheel.right_acceleration = (right - last.right_velocity) / dt;
+        }
+        wheels.push(wheel);
+    }
+    wheels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::Spline;
+    use crate::path::{PathChain, Segment};
+    use eta3_spline::MotionState;
+
+    const CONFIG: TrajectoryConfig = TrajectoryConfig {
+        max_velocity: 3.,
+        max_acceleration: 2.,
+        max_centripetal_acceleration: 2.5,
+    };
+
+    fn state(x: f64, y: f64, t: f64) -> MotionState<f64> {
+        MotionState {
+            x,
+            y,
+            t,
+            k: 0.,
+            dk: 0.,
+        }
+    }
+
+    /// Curves 20 ft out to the left, then backs up 10 ft, in feet like any sampled path.
+    fn out_and_back() -> SampledPath {
+        PathChain::new(Segment::forward(
+            vec![state(0., 0., 0.), state(15., 10., 1.2)],
+            Spline::QuinticHermite,
+            300,
+        ))
+        .then(Segment::reverse(
+            vec![state(15., 10., 1.2), state(12., 0., 1.5)],
+            Spline::QuinticHermite,
+            300,
+        ))
+        .sample()
+        .unwrap()
+    }
+
+    #[test]
+    fn stays_within_limits() {
+        let states = parameterize(&out_and_back(), &CONFIG).unwrap();
+        for s in &states {
+            assert!(s.velocity.abs() <= CONFIG.max_velocity + 1e-9);
+            assert!(s.acceleration.abs() <= CONFIG.max_acceleration + 1e-6);
+            let sideways = s.velocity.powi(2) * s.curvature.abs();
+            assert!(sideways <= CONFIG.max_centripetal_acceleration + 1e-6);
+        }
+        assert!(states.windows(2).all(|w| w[1].time > w[0].time));
+        // fast enough to be worth checking
+        assert!(states
+            .iter()
+            .any(|s| s.velocity > 0.9 * CONFIG.max_velocity));
+    }
+
+    #[test]
+    fn stops_at_the_ends_and_where_direction_reverses() {
+        let states = parameterize(&out_and_back(), &CONFIG).unwrap();
+        assert_eq!(states[0].velocity, 0.);
+        assert_eq!(states[states.len() - 1].velocity, 0.);
+        let turnaround = states
+            .windows(2)
+            .position(|w| w[0].velocity > 0. && w[1].velocity <= 0.)
+            .unwrap();
+        assert_eq!(states[turnaround + 1].velocity, 0.);
+        assert!(states[turnaround + 2..].iter().all(|s| s.velocity <= 0.));
+    }
+
+    #[test]
+    fn outside_wheel_goes_further() {
+        let states = parameterize(&out_and_back(), &CONFIG).unwrap();
+        let wheels = wheel_states(&states, 0.7);
+        for (s, w) in states.iter().zip(&wheels) {
+            assert!((w.left_velocity + w.right_velocity - 2. * s.velocity).abs() < 1e-9);
+            let turning = s.velocity * s.curvature * 0.7;
+            assert!((w.right_velocity - w.left_velocity - turning).abs() < 1e-9);
+        }
+        // the forward leg turns left overall, so the right wheels roll further, then both back up
+        let last = wheels[wheels.len() - 1];
+        let turnaround = states.iter().position(|s| s.velocity < 0.).unwrap() - 1;
+        assert!(wheels[turnaround].right_position > wheels[turnaround].left_position);
+        assert!(last.left_position < wheels[turnaround].left_position);
+        assert!(last.right_position < wheels[turnaround].right_position);
+    }
+
+    #[test]
+    fn too_short() {
+        let mut path = out_and_back();
+        path.points.truncate(1);
+        match parameterize(&path, &CONFIG) {
+            Err(ExportError::TooShort { points: 1 }) => (),
+            other => panic!(
+                "expected a path too short, got {:?}",
+                other.map(|s| s.len())
+            ),
+        }
+    }
+}
@@ -0,0 +1,508 @@
+use crate::field::Field;
+use crate::geometry;
+use crate::path::SampledPath;
+use crate::preview::{corners, feet};
+use crate::robot::Robot;
+use std::fmt;
+
+/// How far, in feet, the robot may press into an element or past the edge of the carpet before
+/// it counts as a collision. Paths that end against a wall put the bumpers right on it.
+const CONTACT: f64 = 0.01;
+
+/// What the drivetrain can manage, in feet and seconds.
+#[derive(Debug, Copy, Clone)]
+pub struct RobotLimits {
+    pub min_turning_radius: f64,
+    /// The speed the robot is assumed to hold through turns.
+    pub cruise_velocity: f64,
+    pub max_angular_velocity: f64,
+    pub max_angular_acceleration: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Limit {
+    TurningRadius,
+    /// Cruise speed times curvature.
+    AngularVelocity,
+    /// Cruise speed squared times the rate of change of curvature.
+    AngularAcceleration,
+}
+
+/// A stretch of path that breaks one of the limits.
+#[derive(Debug, Copy, Clone)]
+pub struct Violation {
+    pub limit: Limit,
+    /// Distance along the path where the stretch starts and ends, in feet.
+    pub start: f64,
+    pub end: f64,
+    /// The worst value over the stretch, in the units of the limit.
+    pub worst: f64,
+    pub allowed: f64,
+}
+
+/// A stretch of path where the robot's outline runs into a field element, or off the carpet when
+/// the element is `field`.
+#[derive(Debug, Clone)]
+pub struct Collision {
+    pub element: String,
+    /// Distance along the path where the stretch starts and ends, in feet.
+    pub start: f64,
+    pub end: f64,
+}
+
+/// How hard a path is to drive, and where it asks too much of the robot.
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// In 1/ft.
+    pub max_curvature: f64,
+    /// In 1/ft^2.
+    pub max_curvature_rate: f64,
+    pub violations: Vec<Violation>,
+    pub collisions: Vec<Collision>,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty() && self.collisions.is_empty()
+    }
+
+    /// How many places the path breaks a limit or runs into something.
+    pub fn problems(&self) -> usize {
+        self.violations.len() + self.collisions.len()
+    }
+}
+
+/// Samples curvature and its rate of change along the driven part of `path` against `robot`'s
+/// limits, and checks that its outline stays on the carpet and clear of `field`'s elements.
+///
+/// Each run driven in one direction is checked on its own, since the robot stops to change
+/// direction and the cusp between runs is not a turn.
+pub fn validate(path: &SampledPath, robot: &Robot, field: &Field) -> Report {
+    let limits = robot.limits();
+    let v = limits.cruise_velocity;
+    let mut report = Report {
+        max_curvature: 0.0,
+        max_curvature_rate: 0.0,
+        violations: Vec::new(),
+        collisions: collisions(path, robot, field),
+    };
+
+    let driven: Vec<_> = path.points.iter().filter(|p| !p.interpolated).collect();
+    let mut runs: Vec<&[_]> = Vec::new();
+    let mut start = 0;
+    for i in 1..=driven.len() {
+        if i == driven.len() || driven[i].reversed != driven[start].reversed {
+            runs.push(&driven[start..i]);
+            start = i;
+        }
+    }
+
+    for run in runs {
+        let points: Vec<(f64, f64)> = run.iter().map(|p| (p.x, p.y)).collect();
+        let profile = geometry::curvature_profile(&points);
+        let offset = run[0].distance;
+        let distance: Vec<f64> = profile.distance.iter().map(|s| s + offset).collect();
+        let curvature: Vec<f64> = profile.curvature.iter().map(|k| k.abs()).collect();
+        let rate: Vec<f64> = profile.curvature_rate().iter().map(|dk| dk.abs()).collect();
+
+        report.max_curvature = curvature
+            .iter()
+            .cloned()
+            .fold(report.max_curvature, f64::max);
+        report.max_curvature_rate = rate
+            .iter()
+            .cloned()
+            .fold(report.max_curvature_rate, f64::max);
+
+        let radius: Vec<f64> = curvature.iter().map(|k| 1. / k).collect();
+        find_violations(
+            &mut report.violations,
+            Limit::TurningRadius,
+            &distance,
+            &radius,
+            limits.min_turning_radius,
+        );
+        let angular_velocity: Vec<f64> = curvature.iter().map(|k| v * k).collect();
+        find_violations(
+            &mut report.violations,
+            Limit::AngularVelocity,
+            &distance,
+            &angular_velocity,
+            limits.max_angular_velocity,
+        );
+        let angular_acceleration: Vec<f64> = rate.iter().map(|dk| v * v * dk).collect();
+        find_violations(
+            &mut report.violations,
+            Limit::AngularAcceleration,
+            &distance[1..],
+            &angular_acceleration,
+            limits.max_angular_acceleration,
+        );
+    }
+    report
+}
+
+/// A rectangle by its center, heading and size, in feet and radians.
+#[derive(Debug, Copy, Clone)]
+struct Rectangle {
+    x: f64,
+    y: f64,
+    heading: f64,
+    length: f64,
+    width: f64,
+}
+
+impl Rectangle {
+    fn corners(&self) -> [(f64, f64); 4] {
+        corners(self.x, self.y, self.heading, self.length, self.width)
+    }
+
+    /// Where `p` is along and across the rectangle, from its center.
+    fn local(&self, p: (f64, f64)) -> (f64, f64) {
+        let (c, s) = (self.heading.cos(), self.heading.sin());
+        let (dx, dy) = (p.0 - self.x, p.1 - self.y);
+        (dx * c + dy * s, dy * c - dx * s)
+    }
+
+    /// Whether `p` is inside, allowing `slack` past the edges.
+    fn contains(&self, p: (f64, f64), slack: f64) -> bool {
+        let (along, across) = self.local(p);
+        along.abs() <= self.length / 2. + slack && across.abs() <= self.width / 2. + slack
+    }
+
+    /// Whether the two overlap by more than `slack`, by looking for an axis of either one that
+    /// separates them.
+    fn overlaps(&self, other: &Rectangle, slack: f64) -> bool {
+        let separated = |a: &Rectangle, b: &Rectangle| {
+            let locals: Vec<(f64, f64)> = b.corners().iter().map(|&p| a.local(p)).collect();
+            let beyond = |value: fn(&(f64, f64)) -> f64, half: f64| {
+                locals.iter().all(|p| value(p) >= half - slack)
+                    || locals.iter().all(|p| value(p) <= slack - half)
+            };
+            beyond(|p| p.0, a.length / 2.) || beyond(|p| p.1, a.width / 2.)
+        };
+        !separated(self, other) && !separated(other, self)
+    }
+}
+
+/// Where the outline of `robot` driving `path` leaves the carpet or hits an element of `field`
+/// that has a size. The carpet is the element `field`, whose position is the middle of its near
+/// edge.
+fn collisions(path: &SampledPath, robot: &Robot, field: &Field) -> Vec<Collision> {
+    let footprint = robot.footprint();
+    let driven: Vec<_> = path.points.iter().filter(|p| !p.interpolated).collect();
+    let n = driven.len();
+    // the outline is symmetric, so it doesn't matter which way the robot faces along the path
+    let outlines: Vec<(f64, Rectangle)> = (0..n)
+        .map(|i| {
+            let (a, b) = (driven[i.saturating_sub(1)], driven[(i + 1).min(n - 1)]);
+            let p = driven[i];
+            let outline = Rectangle {
+                x: p.x,
+                y: p.y,
+                heading: (b.y - a.y).atan2(b.x - a.x),
+                length: feet(footprint.length),
+                width: feet(footprint.width),
+            };
+            (p.distance, outline)
+        })
+        .collect();
+
+    let mut collisions = Vec::new();
+    for element in field.elements() {
+        let (length, width) = (feet(element.length()), feet(element.width()));
+        if length <= 0.0 || width <= 0.0 {
+            continue;
+        }
+        let heading = element.heading().radians();
+        let carpet = element.name == "field";
+        let (x, y) = if carpet {
+            (
+                feet(element.x()) + length / 2. * heading.cos(),
+                feet(element.y()) + length / 2. * heading.sin(),
+            )
+        } else {
+            (feet(element.x()), feet(element.y()))
+        };
+        let bounds = Rectangle {
+            x,
+            y,
+            heading,
+            length,
+            width,
+        };
+        let hits = |outline: &Rectangle| {
+            if carpet {
+                !outline
+                    .corners()
+                    .iter()
+                    .all(|&p| bounds.contains(p, CONTACT))
+            } else {
+                bounds.overlaps(outline, CONTACT)
+            }
+        };
+
+        let mut current: Option<Collision> = None;
+        for (s, outline) in &outlines {
+            if hits(outline) {
+                current
+                    .get_or_insert(Collision {
+                        element: element.name.clone(),
+                        start: *s,
+                        end: *s,
+                    })
+                    .end = *s;
+            } else if let Some(c) = current.take() {
+                collisions.push(c);
+            }
+        }
+        collisions.extend(current);
+    }
+    collisions
+}
+
+/// Merges neighboring samples that break `limit` into one violation each.
+fn find_violations(
+    violations: &mut Vec<Violation>,
+    limit: Limit,
+    distance: &[f64],
+    values: &[f64],
+    allowed: f64,
+) {
+    let breaks = |value: f64| match limit {
+        Limit::TurningRadius => value < allowed,
+        _ => value > allowed,
+    };
+    let mut current: Option<Violation> = None;
+    for (&s, &value) in distance.iter().zip(values) {
+        if breaks(value) {
+            let v = current.get_or_insert(Violation {
+                limit,
+                start: s,
+                end: s,
+                worst: value,
+                allowed,
+            });
+            v.end = s;
+            if breaks_more(limit, value, v.worst) {
+                v.worst = value;
+            }
+        } else if let Some(v) = current.take() {
+            violations.push(v);
+        }
+    }
+    violations.extend(current);
+}
+
+fn breaks_more(limit: Limit, value: f64, worst: f64) -> bool {
+    match limit {
+        Limit::TurningRadius => value < worst,
+        _ => value > worst,
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (what, relation, unit) = match self.limit {
+            Limit::TurningRadius => ("turning radius", "below", "ft"),
+            Limit::AngularVelocity => ("angular velocity", "above", "rad/s"),
+            Limit::AngularAcceleration => ("angular acceleration", "above", "rad/s^2"),
+        };
+        write!(
+            f,
+            "{} {:.2} {} {} {:.2} {} from {:.1} to {:.1} ft",
+            what, self.worst, unit, relation, self.allowed, unit, self.start, self.end
+        )
+    }
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.element == "field" {
+            write!(f, "off the field")?;
+        } else {
+            write!(f, "runs into {}", self.element)?;
+        }
+        write!(f, " from {:.1} to {:.1} ft", self.start, self.end)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "max curvature {:.3} 1/ft, max curvature rate {:.3} 1/ft^2",
+            self.max_curvature, self.max_curvature_rate
+        )?;
+        if self.is_ok() {
+            write!(f, ", within limits")
+        } else {
+            for violation in &self.violations {
+                write!(f, "\n    {}", violation)?;
+            }
+            for collision in &self.collisions {
+                write!(f, "\n    {}", collision)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PathPoint;
+    use std::f64::consts::FRAC_PI_2;
+    use std::fs;
+    use std::path::Path;
+
+    /// A straight drive downfield at `x`, from 5 to 20 ft, short of the scale.
+    fn downfield(x: f64) -> SampledPath {
+        SampledPath {
+            points: (0..=30)
+                .map(|i| PathPoint {
+                    x,
+                    y: 5. + i as f64 * 0.5,
+                    distance: i as f64 * 0.5,
+                    interpolated: false,
+                    reversed: false,
+                })
+                .collect(),
+            end_direction: (0., 1.),
+            markers: Vec::new(),
+        }
+    }
+
+    /// Drives `length` ft downfield from the middle of the alliance wall, turning by the
+    /// curvature in 1/ft at each distance along the way.
+    fn drive(length: f64, curvature: impl Fn(f64) -> f64) -> SampledPath {
+        const STEP: f64 = 0.1;
+        let (mut x, mut y, mut heading) = (0., 5., FRAC_PI_2);
+        let mut points = Vec::new();
+        for i in 0..=(length / STEP).round() as usize {
+            let s = i as f64 * STEP;
+            points.push(PathPoint {
+                x,
+                y,
+                distance: s,
+                interpolated: false,
+                reversed: false,
+            });
+            let turn = curvature(s + STEP / 2.) * STEP;
+            x += STEP * (heading + turn / 2.).cos();
+            y += STEP * (heading + turn / 2.).sin();
+            heading += turn;
+        }
+        SampledPath {
+            points,
+            end_direction: (heading.cos(), heading.sin()),
+            markers: Vec::new(),
+        }
+    }
+
+    /// The 2018 robot with other turning limits, the radius in meters.
+    fn turning(min_radius: f64, max_angular_velocity: f64, max_angular_acceleration: f64) -> Robot {
+        let mut robot: serde_json::Value =
+            serde_json::from_slice(&fs::read("robots/2018.json").unwrap()).unwrap();
+        let turning = &mut robot["turning"];
+        turning["minRadius"] = min_radius.into();
+        turning["maxAngularVelocity"] = max_angular_velocity.into();
+        turning["maxAngularAcceleration"] = max_angular_acceleration.into();
+        serde_json::from_value(robot).unwrap()
+    }
+
+    fn violations(path: &SampledPath, robot: &Robot) -> Vec<Violation> {
+        let field = Field::load(Path::new("fields/2018.json")).unwrap();
+        validate(path, robot, &field).violations
+    }
+
+    /// Asserts `violations` is the one stretch from `start` to `end` that breaks `limit`, give or
+    /// take a few samples, at worst by `worst`.
+    fn assert_one(violations: &[Violation], limit: Limit, start: f64, end: f64, worst: f64) {
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        let v = violations[0];
+        assert_eq!(v.limit, limit);
+        assert!((v.start - start).abs() < 0.3, "{:?}", v);
+        assert!((v.end - end).abs() < 0.3, "{:?}", v);
+        assert!((v.worst - worst).abs() < 0.05 * worst, "{:?}", v);
+    }
+
+    #[test]
+    fn tight_turn_breaks_turning_radius() {
+        // a 2 ft circle, inside the 1 m the robot needs
+        let robot = turning(1., 1e3, 1e3);
+        let violations = violations(&drive(10., |_| 0.5), &robot);
+        assert_one(&violations, Limit::TurningRadius, 0., 10., 2.);
+    }
+
+    #[test]
+    fn fast_turn_breaks_angular_velocity() {
+        // 10 ft/s around a 2 ft circle is 5 rad/s
+        let robot = turning(0.01, 3., 1e3);
+        let violations = violations(&drive(10., |_| 0.5), &robot);
+        assert_one(&violations, Limit::AngularVelocity, 0., 10., 5.);
+    }
+
+    #[test]
+    fn sudden_turn_breaks_angular_acceleration() {
+        // tightening by 0.05 1/ft every foot at 10 ft/s is 5 rad/s^2
+        let robot = turning(0.01, 1e3, 2.);
+        let violations = violations(&drive(10., |s| 0.05 * s), &robot);
+        assert_one(&violations, Limit::AngularAcceleration, 0., 10., 5.);
+    }
+
+    #[test]
+    fn gentle_path_is_not_flagged() {
+        let field = Field::load(Path::new("fields/2018.json")).unwrap();
+        let robot = Robot::load(Path::new("robots/2018.json")).unwrap();
+        let report = validate(&drive(15., |_| 1. / 60.), &robot, &field);
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[test]
+    fn consecutive_breaks_merge() {
+        // two turns on a 2 ft circle, each breaking the limit at every sample along it
+        let turns = |s: f64| {
+            if (5. ..10.).contains(&s) || (13. ..16.).contains(&s) {
+                0.5
+            } else {
+                0.
+            }
+        };
+        let robot = turning(1., 1e3, 1e3);
+        let violations = violations(&drive(20., turns), &robot);
+        assert_eq!(violations.len(), 2, "{:?}", violations);
+        assert_one(&violations[..1], Limit::TurningRadius, 5., 10., 2.);
+        assert_one(&violations[1..], Limit::TurningRadius, 13., 16., 2.);
+    }
+
+    fn collisions_at(x: f64) -> Vec<Collision> {
+        let field = Field::load(Path::new("fields/2018.json")).unwrap();
+        let robot = Robot::load(Path::new("robots/2018.json")).unwrap();
+        validate(&downfield(x), &robot, &field).collisions
+    }
+
+    #[test]
+    fn clear_down_the_middle() {
+        assert!(collisions_at(0.).is_empty());
+    }
+
+    #[test]
+    fn runs_into_the_switch() {
+        let collisions = collisions_at(4.9);
+        assert_eq!(collisions.len(), 1);
+        let c = &collisions[0];
+        assert_eq!(c.element, "rightSwitchPlate");
+        // the plate is 4 ft long centered 13.67 ft downfield, and the robot 3.1 ft long
+        assert!((c.start - (13.67 - 2. - 1.55 - 5.)).abs() < 0.5);
+        assert!((c.end - (13.67 + 2. + 1.55 - 5.)).abs() < 0.5);
+    }
+
+    #[test]
+    fn off_the_field_along_the_wall() {
+        let collisions = collisions_at(12.5);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].element, "field");
+        assert_eq!((collisions[0].start, collisions[0].end), (0., 15.));
+    }
+}
@@ -0,0 +1,259 @@
+//! Regenerates paths as the field, robot profile and path definition files are edited, so an auto
+//! can be tuned with the previews open instead of rebuilding and rerunning for every change.
+
+use crate::autos;
+use crate::batch::{self, PathOutcome};
+use crate::defs::{self, DefError};
+use crate::error::ExportError;
+use crate::field::Field;
+use crate::frames::PathFrame;
+use crate::path::PathChain;
+use crate::robot::Robot;
+use crate::Options;
+use coord_frames::{FrameRegistry, PointData};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often files are checked for changes.
+const POLL: Duration = Duration::from_millis(500);
+
+fn modified(file: &Path) -> Option<SystemTime> {
+    fs::metadata(file).and_then(|m| m.modified()).ok()
+}
+
+/// What was generated last, to tell which outputs an edit affects.
+struct Watcher<'a> {
+    options: Options,
+    out_dir: &'a Path,
+    field: Option<(Field, FrameRegistry<PathFrame>)>,
+    poses: Vec<(String, PointData)>,
+    /// Paths defined in code, which only change with the field.
+    builtin: Vec<String>,
+    /// Each definition file, when it was last read and the paths it defined.
+    files: BTreeMap<PathBuf, (Option<SystemTime>, Vec<String>)>,
+}
+
+impl Watcher<'_> {
+    /// Reloads the field and regenerates everything on it. Definition files are forgotten, so
+    /// the next `sync_files` rereads them all.
+    fn reload_field(&mut self) {
+        self.field = None;
+        self.poses.clear();
+        self.builtin.clear();
+        self.files.clear();
+
+        let field = match Field::load(Path::new(&self.options.field)) {
+            Ok(field) => field,
+            Err(e) => return eprintln!("{}", e),
+        };
+        let robot = &self.options.robot;
+        let defined = autos::define(&field, robot)
+            .and_then(|autos| Ok((autos, autos::frames(&field, robot)?)));
+        let (autos, frames) = match defined {
+            Ok(defined) => defined,
+            Err(e) => return eprintln!("{}", e),
+        };
+        for (name, pose) in &autos.poses {
+            for exporter in &self.options.exporters {
+                if let Err(e) = exporter.export_pose(*pose, name, self.out_dir) {
+                    eprintln!("{}: {}", name, e);
+                }
+            }
+        }
+        let mut paths = autos.paths;
+        if let Some(ref spline) = self.options.spline {
+            for (_, chain) in &mut paths {
+                chain.set_spline(spline);
+            }
+        }
+        let (options, out_dir) = (&self.options, self.out_dir);
+        let outcomes = batch::run(&paths, options.jobs, |(name, chain)| {
+            crate::write_path(options, name, chain, &field, out_dir)
+        });
+        for outcome in outcomes {
+            self.builtin.push(outcome.name.clone());
+            print_outcome(outcome);
+        }
+        self.poses = autos.poses;
+        self.field = Some((field, frames));
+    }
+
+    /// Rereads the robot profile, keeping the robot there was if it can't be read. Returns
+    /// whether the robot changed.
+    fn reload_robot(&mut self) -> bool {
+        match Robot::load(Path::new(&self.options.robot_file)) {
+            Ok(robot) => {
+                self.options.set_robot(robot);
+                true
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                false
+            }
+        }
+    }
+
+    /// Rereads the definition files that appeared or changed since they were last read and
+    /// regenerates their paths. Returns whether anything changed.
+    fn sync_files(&mut self) -> bool {
+        let listing = match defs::files(&self.options.paths) {
+            Ok(listing) => listing,
+            Err(e) => {
+                eprintln!("{}", e);
+                return false;
+            }
+        };
+        let mut changed = false;
+        let before = self.files.len();
+        self.files.retain(|file, _| {
+            let kept = listing.contains(file);
+            if !kept {
+                println!("{} removed, leaving its outputs in place", file.display());
+            }
+            kept
+        });
+        changed |= self.files.len() != before;
+
+        for file in listing {
+            let time = modified(&file);
+            if self.files.get(&file).is_some_and(|(read, _)| *read == time) {
+                continue;
+            }
+            changed = true;
+            // its own paths don't clash with themselves
+            if self.files.remove(&file).is_some() {
+                println!("{} changed", file.display());
+            }
+            let mut names = Vec::new();
+            if let Some((ref field, ref frames)) = self.field {
+                match defs::load(&file, frames, &self.options.robot) {
+                    Ok(paths) => {
+                        for (name, mut chain) in paths {
+                            if self.defined(&name) || names.contains(&name) {
+                                eprintln!("{}", DefError::Duplicate(name));
+                                continue;
+                            }
+                            self.write(&name, &mut chain, field);
+                            names.push(name);
+                        }
+                    }
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            self.files.insert(file, (time, names));
+        }
+        changed
+    }
+
+    fn defined(&self, name: &str) -> bool {
+        self.builtin
+            .iter()
+            .chain(self.files.values().flat_map(|(_, names)| names))
+            .any(|defined| defined == name)
+    }
+
+    fn write(&self, name: &str, chain: &mut PathChain, field: &Field) {
+        if let Some(ref spline) = self.options.spline {
+            chain.set_spline(spline);
+        }
+        print_outcome(crate::write_path(
+            &self.options,
+            name,
+            chain,
+            field,
+            self.out_dir,
+        ));
+    }
+
+    /// Rewrites the Java class and the routine manifest, since paths may have come or gone.
+    fn write_java(&self) {
+        let names = || {
+            self.builtin
+                .iter()
+                .chain(self.files.values().flat_map(|(_, names)| names))
+        };
+        if let Err(e) = crate::write_java(&self.options, &self.poses, names(), self.out_dir) {
+            eprintln!("{}", e);
+        }
+        match crate::write_routines(&self.options, names(), self.out_dir) {
+            Ok(expansion) => {
+                for unrunnable in expansion.unrunnable {
+                    let name = unrunnable.name();
+                    let legs = unrunnable.missing;
+                    eprintln!("{}: {}", name, ExportError::MissingLegs { legs });
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+fn print_outcome(outcome: PathOutcome) {
+    if let Some(report) = outcome.report {
+        println!("{}: {}", outcome.name, report);
+    }
+    for e in outcome.failures {
+        eprintln!("{}: {}", outcome.name, e);
+    }
+}
+
+/// Generates everything, then regenerates whatever an edit to the field, the robot profile or a
+/// definition file affects until killed. Only returns, with `false`, if there is nowhere to write
+/// to.
+pub fn watch(options: Options) -> bool {
+    let out_dir = Path::new("out");
+    if let Err(e) = crate::prepare_output(&options, out_dir) {
+        eprintln!("{}", e);
+        return false;
+    }
+
+    let field_file = PathBuf::from(&options.field);
+    let robot_file = PathBuf::from(&options.robot_file);
+    let paths = options.paths.clone();
+    let mut watcher = Watcher {
+        options,
+        out_dir,
+        field: None,
+        poses: Vec::new(),
+        builtin: Vec::new(),
+        files: BTreeMap::new(),
+    };
+    let mut field_time = None;
+    // the robot was loaded along with the options
+    let mut robot_time = modified(&robot_file);
+    let mut first = true;
+    loop {
+        let time = modified(&field_file);
+        let mut changed = false;
+        // everything is placed, sampled and checked for the robot, so it all depends on it
+        let mut robot_changed = false;
+        if modified(&robot_file) != robot_time {
+            println!("{} changed", robot_file.display());
+            robot_time = modified(&robot_file);
+            robot_changed = watcher.reload_robot();
+        }
+        if first || time != field_time || robot_changed {
+            if !first && time != field_time {
+                println!("{} changed", field_file.display());
+            }
+            watcher.reload_field();
+            field_time = time;
+            first = false;
+            changed = true;
+        }
+        changed |= watcher.sync_files();
+        if changed {
+            watcher.write_java();
+            println!(
+                "watching {}, {} and {} for changes",
+                field_file.display(),
+                robot_file.display(),
+                paths.display()
+            );
+        }
+        thread::sleep(POLL);
+    }
+}
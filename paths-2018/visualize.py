@@ -1,5 +1,6 @@
 #!/usr/bin/env python3
 
+import json
 import sys
 import matplotlib.pyplot as plt
 from math import sqrt
@@ -35,11 +36,25 @@ def box(xy, norm_len, axial_len):
 IMAGE_HEIGHT_FT = 27.0
 IMAGE_WIDTH_FT = 54.0
 
-ROBOT_WIDTH = 2.83333333
-ROBOT_LENGTH = 3.125
+FEET_PER_METER = 3.28084
+DEFAULT_ROBOT = "robots/2018.json"
+
+
+def robot_size(file):
+    """The robot's length and width with bumpers, in feet, from its profile."""
+    with open(file, 'r') as f:
+        robot = json.load(f)
+    bumpers = 2 * robot["bumper"]
+    return ((robot["frame"]["length"] + bumpers) * FEET_PER_METER,
+            (robot["frame"]["width"] + bumpers) * FEET_PER_METER)
 
 
 def main():
+    if len(sys.argv) not in (2, 3):
+        sys.exit("usage: visualize.py <file.114path> [<robot.json>]")
+    robot_length, robot_width = robot_size(
+        sys.argv[2] if len(sys.argv) == 3 else DEFAULT_ROBOT)
+
     with open(sys.argv[1], 'r') as file:
         lines = file.readlines()
     lines = lines[1:-1]  # header and vector footer
@@ -49,7 +64,7 @@ def main():
               for datum in data if not to_bool(datum[3])]
     xy_interp = [(float(datum[0]), float(datum[1]))
                  for datum in data if to_bool(datum[3])]
-    bounds = box(xy_pts, ROBOT_WIDTH, ROBOT_LENGTH)
+    bounds = box(xy_pts, robot_width, robot_length)
 
     _fig, ax = plt.subplots()
     image = plt.imread("fieldCropped.png")